@@ -0,0 +1,169 @@
+use std::str::FromStr;
+
+use crate::{
+    board::{BoardData, LoadOptions},
+    palette::ColorId,
+    resample::ResampleMode,
+};
+
+pub const USAGE: &str = "\
+Usage: pixel-tui [OPTIONS] <IMAGE>
+
+Paint-by-number puzzles in the terminal.
+
+Arguments:
+  <IMAGE>              Puzzle image to load (Netpbm, PNG, GIF, BMP or QOI)
+
+Options:
+  -c, --color <N>      Initially selected palette color (1-based)
+      --colors <N>     Reduce the image to at most N colors
+      --dither         Dither when reducing colors
+  -s, --size <WxH>     Shrink the image to fit in WxH cells
+      --resample <M>   Shrinking mode: nearest, box or majority [default: box]
+      --half-blocks    Start in half block render mode, toggled with 'm'
+      --allow-wrong    Let cells be painted the wrong color, marked as mistakes until erased
+      --fps <N>        Maximum frames per second [default: 60]
+      --autosave <S>   Save progress every S seconds, 0 to only save on quit [default: 30]
+      --no-save        Neither restore nor save progress
+  -h, --help           Print help
+  -V, --version        Print version";
+
+pub struct Args {
+    pub image_path: String,
+    pub color: Option<ColorId>,
+    pub colors: Option<usize>,
+    pub dither: bool,
+    pub size: Option<(usize, usize)>,
+    pub resample: ResampleMode,
+    pub half_blocks: bool,
+    pub allow_wrong: bool,
+    pub fps: u64,
+    pub autosave: u64,
+    pub save: bool,
+}
+
+pub enum Command {
+    Run(Args),
+    Help,
+    Version,
+}
+
+fn parse_value<T: FromStr>(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<T, String> {
+    if let Some(value) = args.next() {
+        value
+            .parse::<T>()
+            .map_err(|_| format!("Invalid value '{value}' for {flag}"))
+    } else {
+        Err(format!("Expected a value for {flag}"))
+    }
+}
+
+fn parse_size(
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<(usize, usize), String> {
+    let Some(value) = args.next() else {
+        return Err(format!("Expected a value for {flag}"));
+    };
+
+    value
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some((w.parse::<usize>().ok()?, h.parse::<usize>().ok()?)))
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .ok_or_else(|| format!("Invalid value '{value}' for {flag}, expected WxH"))
+}
+
+impl Args {
+    pub fn load_options(&self) -> LoadOptions {
+        LoadOptions {
+            size: self.size,
+            resample: self.resample,
+            colors: self.colors,
+            dither: self.dither,
+        }
+    }
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Command, String> {
+        let mut image_path: Option<String> = None;
+        let mut color: Option<usize> = None;
+        let mut colors: Option<usize> = None;
+        let mut dither = false;
+        let mut size: Option<(usize, usize)> = None;
+        let mut resample = ResampleMode::default();
+        let mut half_blocks = false;
+        let mut allow_wrong = false;
+        let mut fps: u64 = 60;
+        let mut autosave: u64 = 30;
+        let mut save = true;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-V" | "--version" => return Ok(Command::Version),
+                "-c" | "--color" => color = Some(parse_value(&mut args, &arg)?),
+                "--colors" => colors = Some(parse_value(&mut args, &arg)?),
+                "--dither" => dither = true,
+                "-s" | "--size" => size = Some(parse_size(&mut args, &arg)?),
+                "--resample" => resample = parse_value(&mut args, &arg)?,
+                "--half-blocks" => half_blocks = true,
+                "--allow-wrong" => allow_wrong = true,
+                "--fps" => fps = parse_value(&mut args, &arg)?,
+                "--autosave" => autosave = parse_value(&mut args, &arg)?,
+                "--no-save" => save = false,
+                _ if arg.starts_with('-') => {
+                    return Err(format!("Unknown option '{arg}'"));
+                }
+                _ => {
+                    if image_path.is_some() {
+                        return Err(format!("Unexpected argument '{arg}'"));
+                    }
+                    image_path = Some(arg);
+                }
+            }
+        }
+
+        let Some(image_path) = image_path else {
+            return Err("Missing <IMAGE> argument".to_string());
+        };
+
+        if let Some(color) = color {
+            if color == 0 || color > BoardData::MAX_COLORS {
+                return Err(format!(
+                    "--color must be between 1 and {}, got {color}",
+                    BoardData::MAX_COLORS
+                ));
+            }
+        }
+        if let Some(colors) = colors {
+            if colors == 0 || colors > BoardData::MAX_COLORS {
+                return Err(format!(
+                    "--colors must be between 1 and {}, got {colors}",
+                    BoardData::MAX_COLORS
+                ));
+            }
+        }
+        if dither && colors.is_none() {
+            return Err("--dither requires --colors".to_string());
+        }
+        if fps == 0 {
+            return Err("--fps must be greater than 0".to_string());
+        }
+
+        Ok(Command::Run(Self {
+            image_path,
+            color: color.map(|color| ColorId((color - 1) as u16)),
+            colors,
+            dither,
+            size,
+            resample,
+            half_blocks,
+            allow_wrong,
+            fps,
+            autosave,
+            save,
+        }))
+    }
+}
@@ -1,14 +1,139 @@
-// TODO: single char top/bottom two different colors for bigger cells
-
-use std::{any::type_name, fs, io, iter::Peekable, str::FromStr};
+use std::{
+    collections::HashMap,
+    error, fmt, fs, io,
+    ops::Range,
+    time::{Duration, Instant},
+};
 
-use crossterm::{cursor, event::MouseButton, queue, style, terminal};
+use crossterm::{
+    event::{KeyCode, KeyModifiers, MouseButton},
+    style::Color,
+};
 
 use crate::{
+    formats,
+    history::{CellChange, History},
     input::Input,
-    palette::{Palette, RGB},
+    layout::Layout,
+    palette::{ColorId, Palette, RGB},
+    quantize,
+    resample::{self, ResampleMode},
+    screen::{Screen, Style},
 };
 
+#[derive(Debug, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub offset: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, byte {}", self.line, self.offset)
+    }
+}
+
+#[derive(Debug)]
+pub enum BoardLoadError {
+    Io(io::Error),
+    UnknownFormat,
+    #[cfg(not(feature = "decoders"))]
+    UnsupportedFormat(&'static str),
+    #[cfg(feature = "decoders")]
+    Decode {
+        format: &'static str,
+        message: String,
+    },
+    BadMagic {
+        position: Position,
+    },
+    BadHeader {
+        position: Position,
+        message: String,
+    },
+    BadPixelData {
+        position: Position,
+        message: String,
+    },
+    Truncated {
+        position: Position,
+    },
+    TooManyColors {
+        x: usize,
+        y: usize,
+        max: usize,
+    },
+    Empty,
+}
+
+impl fmt::Display for BoardLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::UnknownFormat => write!(f, "Unknown image format"),
+            #[cfg(not(feature = "decoders"))]
+            Self::UnsupportedFormat(format) => write!(
+                f,
+                "{format} images are not supported by this build, enable the `decoders` feature"
+            ),
+            #[cfg(feature = "decoders")]
+            Self::Decode { format, message } => {
+                write!(f, "Could not decode {format} image: {message}")
+            }
+            Self::BadMagic { position } => write!(
+                f,
+                "Not a Netpbm file, expected a magic number from P1 to P7 ({position})"
+            ),
+            Self::BadHeader { position, message } => {
+                write!(f, "Invalid header: {message} ({position})")
+            }
+            Self::BadPixelData { position, message } => {
+                write!(f, "Invalid pixel data: {message} ({position})")
+            }
+            Self::Truncated { position } => {
+                write!(f, "Pixel data is truncated ({position})")
+            }
+            Self::TooManyColors { x, y, max } => write!(
+                f,
+                "Image has more than {max} colors, the first extra one is at pixel ({x}, {y})"
+            ),
+            Self::Empty => write!(f, "Image has no opaque pixels"),
+        }
+    }
+}
+
+impl error::Error for BoardLoadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for BoardLoadError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+// transparent pixels are None
+pub struct ImageData {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Option<RGB>>,
+}
+
+#[derive(Default)]
+pub struct LoadOptions {
+    // shrink the image to fit in this many cells, before building the palette
+    pub size: Option<(usize, usize)>,
+    pub resample: ResampleMode,
+    // reduce the image to at most this many colors
+    pub colors: Option<usize>,
+    pub dither: bool,
+}
+
 pub struct BoardDataColor {
     pub rgb: RGB,
     pub count: u32,
@@ -18,241 +143,1192 @@ pub struct BoardData {
     pub width: usize,
     pub height: usize,
 
-    pub pixels: Vec<Vec<u8>>,
+    pub pixels: Vec<Vec<Option<ColorId>>>,
     pub colors: Vec<BoardDataColor>,
 }
 
 impl BoardData {
-    pub fn from_pixels(width: usize, height: usize, image_pixels: Vec<RGB>) -> Self {
+    pub const MAX_COLORS: usize = ColorId::COUNT;
+
+    pub fn from_pixels(
+        width: usize,
+        height: usize,
+        image_pixels: Vec<Option<RGB>>,
+    ) -> Result<Self, BoardLoadError> {
         let mut colors: Vec<BoardDataColor> = Vec::new();
-        let mut pixels: Vec<Vec<u8>> = vec![vec![0; width]; height];
+        let mut color_ids: HashMap<RGB, ColorId> = HashMap::new();
+        let mut pixels: Vec<Vec<Option<ColorId>>> = vec![vec![None; width]; height];
 
         for y in 0..height {
             for x in 0..width {
-                let pixel = image_pixels[y * width + x];
-                if let Some(color_index) = colors.iter().position(|c| c.rgb == pixel) {
-                    pixels[y][x] = color_index as u8;
-                    colors[color_index].count += 1;
+                let Some(pixel) = image_pixels[y * width + x] else {
+                    continue;
+                };
+
+                if let Some(&color) = color_ids.get(&pixel) {
+                    pixels[y][x] = Some(color);
+                    colors[color.index()].count += 1;
                 } else {
+                    if colors.len() == Self::MAX_COLORS {
+                        return Err(BoardLoadError::TooManyColors {
+                            x,
+                            y,
+                            max: Self::MAX_COLORS,
+                        });
+                    }
+
+                    let color = ColorId(colors.len() as u16);
                     colors.push(BoardDataColor {
                         rgb: pixel,
                         count: 1,
                     });
-                    pixels[y][x] = (colors.len() - 1) as u8;
+                    color_ids.insert(pixel, color);
+                    pixels[y][x] = Some(color);
                 }
             }
         }
 
-        Self {
+        if colors.is_empty() {
+            return Err(BoardLoadError::Empty);
+        }
+
+        Ok(Self {
             width,
             height,
             pixels,
             colors,
-        }
+        })
     }
 
-    fn parse_next_token<'a, T: FromStr>(
-        tokens: &mut Peekable<impl Iterator<Item = &'a str>>,
-        expected: &str,
-    ) -> Result<T, String> {
-        if let Some(token) = tokens.next() {
-            if let Ok(parsed_token) = token.parse::<T>() {
-                Ok(parsed_token)
-            } else {
-                Err(format!(
-                    "Invalid {expected} received, expected {} but got {token}",
-                    type_name::<T>()
-                ))
-            }
-        } else {
-            Err(format!("Expected {expected}, but got EOF"))
-        }
-    }
-
-    // TODO: handle errors
-    pub fn from_ppm_file(file_path: &str) -> io::Result<Self> {
-        let mut pixels: Vec<RGB> = Vec::new();
+    pub fn from_image(image: ImageData, options: &LoadOptions) -> Result<Self, BoardLoadError> {
+        let image = match options.size {
+            Some((width, height)) => resample::resample(image, width, height, options.resample),
+            None => image,
+        };
+        let image = match options.colors {
+            Some(colors) => quantize::quantize(image, colors, options.dither),
+            None => image,
+        };
 
-        let tokens = fs::read_to_string(file_path)?
-            .split('\n')
-            .filter(|l| !l.starts_with('#'))
-            .map(|l| l.to_string())
-            .collect::<Vec<String>>()
-            .join(" ");
-        let mut tokens = tokens.split_whitespace().peekable();
-
-        tokens.next(); // P3
-        let width = Self::parse_next_token::<usize>(&mut tokens, "u16 width").unwrap();
-        let height = Self::parse_next_token::<usize>(&mut tokens, "u16 height").unwrap();
-        let _max_color = Self::parse_next_token::<u8>(&mut tokens, "u8 max_color").unwrap();
+        Self::from_pixels(image.width, image.height, image.pixels)
+    }
 
-        for _ in 0..width * height {
-            let r = Self::parse_next_token::<u8>(&mut tokens, "u8 red").unwrap();
-            let g = Self::parse_next_token::<u8>(&mut tokens, "u8 green").unwrap();
-            let b = Self::parse_next_token::<u8>(&mut tokens, "u8 blue").unwrap();
+    pub fn from_file(file_path: &str, options: &LoadOptions) -> Result<Self, BoardLoadError> {
+        let image = formats::decode(file_path, &fs::read(file_path)?)?;
 
-            pixels.push(RGB { r, g, b });
-        }
-
-        Ok(Self::from_pixels(width, height, pixels))
+        Self::from_image(image, options)
     }
 }
 
 #[derive(Clone, Copy)]
 pub struct Cell {
-    pub color: u8,
-    pub filled: bool,
+    // None for cells that are not part of the puzzle
+    pub color: Option<ColorId>,
+    // color the cell was painted with, which is not always the right one
+    // when wrong colors are allowed
+    pub painted: Option<ColorId>,
+}
+
+impl Cell {
+    pub fn is_correct(&self) -> bool {
+        self.painted.is_some() && self.painted == self.color
+    }
+
+    pub fn is_wrong(&self) -> bool {
+        self.painted.is_some() && self.painted != self.color
+    }
 }
 
 pub struct Board {
-    pub x: i16,
-    pub y: i16,
+    // screen position of the top left corner, wider than the screen
+    // coordinates since big boards zoomed in reach far off screen
+    pub x: i32,
+    pub y: i32,
+    pub zoom: usize,
+    pub render_mode: RenderMode,
 
     pub cells: Vec<Vec<Cell>>,
     pub width: usize,
     pub height: usize,
     pub palette: Palette,
+    pub history: History,
+
+    pub tool: Tool,
+    // whether the bucket also spreads to diagonal neighbours
+    pub fill_diagonal: bool,
+    // whether cells can be painted with a color other than their own
+    pub allow_wrong: bool,
+    // wrong colors painted so far, fixed or not
+    pub mistakes: u32,
+    // cells currently painted the wrong color
+    pub wrong_cells: usize,
+
+    // whether unfilled pixels of the selected color stand out from the rest
+    pub highlight: bool,
+    // selected color as of the last update, None before the first one so
+    // the color picked on startup doesn't turn highlighting on
+    last_selected_color: Option<ColorId>,
+
+    // last terminal cell painted or erased by the current drag, the next
+    // sample draws a line from it so fast drags leave no gaps
+    drag_from: Option<(MouseButton, (u16, u16))>,
+
+    // keyboard cursor, shown once it is moved with the keyboard
+    pub cursor: (usize, usize),
+    pub show_cursor: bool,
+
+    // pixel jumped to with 'n' or 'N', blinking for a moment from when it
+    // was found
+    blink: Option<((usize, usize), Instant)>,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tool {
+    // paints the pixels under the mouse or cursor
+    #[default]
+    Brush,
+    // paints the connected region of the selected color
+    Bucket,
+    // clears the pixels under the mouse or cursor
+    Eraser,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    // whole terminal cells per pixel, quadrants when zoomed out
+    #[default]
+    Blocks,
+    // top and bottom halves of each terminal cell drawn separately with
+    // '▀', so every zoom level has square pixels
+    HalfBlocks,
+}
+
+impl RenderMode {
+    // pixel sizes in half terminal cells
+    pub fn zoom_levels(self) -> &'static [(u16, u16)] {
+        match self {
+            // from 2x2 pixels per terminal cell up to 8x4 terminal cells per pixel
+            Self::Blocks => &[(1, 1), (2, 1), (4, 2), (8, 4), (12, 6), (16, 8)],
+            // from 1x2 pixels per terminal cell up to 8x4 terminal cells per pixel
+            Self::HalfBlocks => &[(2, 1), (4, 2), (6, 3), (8, 4), (12, 6), (16, 8)],
+        }
+    }
 }
 
-const CELL_NUMBERS: [&str; 9] = [
-    "\u{31}\u{FE0F}",
-    "\u{32}\u{FE0F}",
-    "\u{33}\u{FE0F}",
-    "\u{34}\u{FE0F}",
-    "\u{35}\u{FE0F}",
-    "\u{36}\u{FE0F}",
-    "\u{37}\u{FE0F}",
-    "\u{38}\u{FE0F}",
-    "\u{39}\u{FE0F}",
+// 2x1 terminal cells per pixel
+const DEFAULT_CELL_SIZE: (u16, u16) = (4, 2);
+
+const UNFILLED_COLOR: Color = Color::Rgb {
+    r: 48,
+    g: 48,
+    b: 48,
+};
+
+// how bright unfilled pixels and their numbers are in half block mode,
+// relative to the color they expect
+const UNFILLED_BRIGHTNESS: f32 = 0.25;
+const HINT_BRIGHTNESS: f32 = 0.6;
+
+// wrongly painted pixels are crossed out when there is room, and tinted
+// towards red when there isn't
+const MISTAKE_GLYPH: &str = "╳";
+const MISTAKE_TINT: RGB = RGB { r: 255, g: 0, b: 0 };
+const MISTAKE_TINT_AMOUNT: f32 = 0.5;
+
+// with highlighting on, unfilled pixels of the selected color get a
+// background of that color moved towards grey so dark and light colors both
+// show, everything else is dimmed
+const HIGHLIGHT_GREY: RGB = RGB {
+    r: 128,
+    g: 128,
+    b: 128,
+};
+const HIGHLIGHT_GREY_AMOUNT: f32 = 0.3;
+const DIM_BRIGHTNESS: f32 = 0.4;
+
+// a pixel jumped to blinks for this long, switching every period
+const BLINK_DURATION: Duration = Duration::from_millis(1200);
+const BLINK_PERIOD: Duration = Duration::from_millis(200);
+
+// indexed by a mask of top left, top right, bottom left and bottom right
+const QUADRANTS: [&str; 16] = [
+    " ", "▘", "▝", "▀", "▖", "▌", "▞", "▛", "▗", "▚", "▐", "▜", "▄", "▙", "▟", "█",
 ];
 
-impl Board {
-    pub const CELL_WIDTH: u16 = 2;
-    pub const CELL_HEIGHT: u16 = 1;
+// black or white, whichever reads better on the given background
+fn contrast(bg: Color) -> Color {
+    match bg {
+        Color::Rgb { r, g, b } if RGB::new(r, g, b).brightness() > 0.5 => Color::Black,
+        Color::White => Color::Black,
+        _ => Color::White,
+    }
+}
 
+// terminal cells on the line between two cells, both ends included
+fn line(from: (u16, u16), to: (u16, u16)) -> Vec<(u16, u16)> {
+    let (mut x, mut y) = (from.0 as i32, from.1 as i32);
+    let (x1, y1) = (to.0 as i32, to.1 as i32);
+    let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+    let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+
+    let mut cells = Vec::new();
+    let mut error = dx + dy;
+    loop {
+        cells.push((x as u16, y as u16));
+        if (x, y) == (x1, y1) {
+            return cells;
+        }
+
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+impl Board {
     pub fn new(data: BoardData) -> Self {
         let mut cells = vec![
             vec![
                 Cell {
-                    color: 0,
-                    filled: false
+                    color: None,
+                    painted: None
                 };
                 data.width
             ];
             data.height
         ];
 
-        for py in 0..data.height {
-            for px in 0..data.width {
-                cells[py][px].color = data.pixels[py][px];
+        for (row, pixels) in cells.iter_mut().zip(&data.pixels) {
+            for (cell, &pixel) in row.iter_mut().zip(pixels) {
+                cell.color = pixel;
             }
         }
 
         Self {
             x: 0,
             y: 0,
+            zoom: RenderMode::default()
+                .zoom_levels()
+                .iter()
+                .position(|&size| size == DEFAULT_CELL_SIZE)
+                .unwrap_or(0),
+            render_mode: RenderMode::default(),
 
             width: data.width,
             height: data.height,
             cells,
             palette: Palette::new(data.colors),
+            history: History::default(),
+
+            tool: Tool::default(),
+            fill_diagonal: false,
+            allow_wrong: false,
+            mistakes: 0,
+            wrong_cells: 0,
+
+            highlight: false,
+            last_selected_color: None,
+
+            drag_from: None,
+
+            cursor: (0, 0),
+            show_cursor: false,
+
+            blink: None,
         }
     }
 
-    pub fn width(&self) -> u16 {
-        self.cells[0].len() as u16
+    pub fn get(&self, px: usize, py: usize) -> Cell {
+        self.cells[py][px]
+    }
+
+    pub fn contains(&self, px: i32, py: i32) -> bool {
+        px >= 0 && px < self.width as i32 && py >= 0 && py < self.height as i32
+    }
+
+    // size of a pixel in half terminal cells
+    pub fn cell_size(&self) -> (u16, u16) {
+        self.render_mode.zoom_levels()[self.zoom]
+    }
+
+    // room for color labels in unfilled pixels, shared with the palette
+    // so both show the same labels
+    pub fn label_width(&self) -> usize {
+        ((self.cell_size().0 / 2) as usize).clamp(1, 4)
+    }
+
+    // pixels covered by a terminal cell, more than one when zoomed out
+    fn pixels_at(&self, col: u16, row: u16) -> (Range<i32>, Range<i32>) {
+        let (w, h) = self.cell_size();
+        let (w, h) = (w as i32, h as i32);
+        let hx = 2 * (col as i32 - self.x);
+        let hy = 2 * (row as i32 - self.y);
+
+        (
+            hx.div_euclid(w)..(hx + 1).div_euclid(w) + 1,
+            hy.div_euclid(h)..(hy + 1).div_euclid(h) + 1,
+        )
+    }
+
+    // columns and rows of the terminal cells covering a pixel, at least one
+    // of each, which may be far off screen
+    fn pixel_rect(&self, px: usize, py: usize) -> (Range<i32>, Range<i32>) {
+        let (w, h) = self.cell_size();
+        let (w, h) = (w as i32, h as i32);
+        let (px, py) = (px as i32, py as i32);
+
+        (
+            self.x + px * w / 2..self.x + ((px + 1) * w - 1) / 2 + 1,
+            self.y + py * h / 2..self.y + ((py + 1) * h - 1) / 2 + 1,
+        )
+    }
+
+    fn move_cursor(&mut self, dx: isize, dy: isize, layout: &Layout) {
+        let (cx, cy) = self.cursor;
+        self.cursor = (
+            cx.saturating_add_signed(dx).min(self.width - 1),
+            cy.saturating_add_signed(dy).min(self.height - 1),
+        );
+        self.show_cursor = true;
+
+        self.scroll_to_cursor(layout);
+    }
+
+    // pans just enough to bring the cursor into view, above the palette
+    fn scroll_to_cursor(&mut self, layout: &Layout) {
+        let area = &layout.board;
+        let (cols, rows) = self.pixel_rect(self.cursor.0, self.cursor.1);
+
+        let (left, right) = (area.x as i32, area.x as i32 + area.width as i32);
+        if cols.start < left {
+            self.x += left - cols.start;
+        } else if cols.end > right {
+            self.x -= cols.end - right;
+        }
+
+        let (top, bottom) = (area.y as i32, area.y as i32 + area.height as i32);
+        if rows.start < top {
+            self.y += top - rows.start;
+        } else if rows.end > bottom {
+            self.y -= rows.end - bottom;
+        }
     }
 
-    pub fn height(&self) -> u16 {
-        self.cells.len() as u16
+    // moves the cursor to the next unfilled pixel of the selected color in
+    // reading order, or the previous one going backwards, wrapping around
+    // the board, and pans so it is in the middle of the board area
+    fn find_remaining(&mut self, backwards: bool, layout: &Layout) {
+        let color = Some(self.palette.selected_color);
+        let count = self.width * self.height;
+        let start = self.cursor.1 * self.width + self.cursor.0;
+
+        // the cursor pixel itself comes last, so it is found again when it
+        // is the only one left
+        let found = (1..=count)
+            .map(|step| {
+                if backwards {
+                    (start + count - step) % count
+                } else {
+                    (start + step) % count
+                }
+            })
+            .map(|i| (i % self.width, i / self.width))
+            .find(|&(px, py)| {
+                let cell = self.cells[py][px];
+                cell.painted.is_none() && cell.color == color
+            });
+        let Some((px, py)) = found else {
+            return;
+        };
+
+        self.cursor = (px, py);
+        self.blink = Some(((px, py), Instant::now()));
+
+        let (col, row) = layout.board_center();
+        self.restore_anchor(col, row, (px as f32 + 0.5, py as f32 + 0.5));
     }
 
-    pub fn get(&self, px: u16, py: u16) -> Cell {
-        self.cells[py as usize][px as usize]
+    // size of the whole board in terminal cells
+    pub fn size_on_screen(&self) -> (i32, i32) {
+        let (w, h) = self.cell_size();
+
+        (
+            ((self.width * w as usize).div_ceil(2)) as i32,
+            ((self.height * h as usize).div_ceil(2)) as i32,
+        )
     }
 
-    pub fn contains(&self, px: i16, py: i16) -> bool {
-        px >= 0 && px < self.width() as i16 && py >= 0 && py < self.height() as i16
+    pub fn center(&mut self, layout: &Layout) {
+        let (width, height) = self.size_on_screen();
+        let area = &layout.board;
+
+        self.x = area.x as i32 + (area.width as i32 - width) / 2;
+        self.y = area.y as i32 + (area.height as i32 - height) / 2;
     }
 
-    pub fn update(&mut self, input: &Input) -> io::Result<()> {
-        self.palette.update(input)?;
+    // centers the board when none of it is left in the board area, like
+    // after restoring progress saved in a bigger terminal
+    pub fn keep_in_view(&mut self, layout: &Layout) {
+        let (width, height) = self.size_on_screen();
+        let area = &layout.board;
+
+        if self.x + width <= area.x as i32
+            || self.x >= area.x as i32 + area.width as i32
+            || self.y + height <= area.y as i32
+            || self.y >= area.y as i32 + area.height as i32
+        {
+            self.center(layout);
+        }
+    }
+
+    // keeps whatever was in the middle of the board area there when the
+    // terminal changes size
+    pub fn resize(&mut self, old: &Layout, new: &Layout) {
+        let (col, row) = old.board_center();
+        let anchor = self.anchor(col, row);
+
+        let (col, row) = new.board_center();
+        self.restore_anchor(col, row, anchor);
+    }
+
+    // position of the center of a terminal cell on the board, in pixels
+    fn anchor(&self, col: u16, row: u16) -> (f32, f32) {
+        let (w, h) = self.cell_size();
+
+        (
+            (2.0 * (col as f32 - self.x as f32) + 1.0) / w as f32,
+            (2.0 * (row as f32 - self.y as f32) + 1.0) / h as f32,
+        )
+    }
+
+    // moves the board so `anchor` is back under the given terminal cell
+    fn restore_anchor(&mut self, col: u16, row: u16, anchor: (f32, f32)) {
+        let (w, h) = self.cell_size();
+
+        self.x = (col as f32 - (anchor.0 * w as f32 - 1.0) / 2.0).round() as i32;
+        self.y = (row as f32 - (anchor.1 * h as f32 - 1.0) / 2.0).round() as i32;
+    }
+
+    // zooms in or out by `delta` levels, keeping the pixel under the given
+    // terminal cell in place
+    pub fn zoom_at(&mut self, col: u16, row: u16, delta: i16) {
+        let levels = self.render_mode.zoom_levels().len() as i16;
+        let zoom = (self.zoom as i16 + delta).clamp(0, levels - 1) as usize;
+        if zoom == self.zoom {
+            return;
+        }
+
+        let anchor = self.anchor(col, row);
+        self.zoom = zoom;
+        self.restore_anchor(col, row, anchor);
+    }
+
+    // switches render mode, keeping the closest pixel size and the pixel
+    // under the given terminal cell in place
+    pub fn set_render_mode(&mut self, mode: RenderMode, col: u16, row: u16) {
+        let anchor = self.anchor(col, row);
+        let (w, _) = self.cell_size();
+
+        self.zoom = mode
+            .zoom_levels()
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, &(level_w, _))| level_w.abs_diff(w))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        self.render_mode = mode;
+        self.restore_anchor(col, row, anchor);
+    }
+
+    pub fn recount_painted(&mut self) {
+        for color in &mut self.palette.colors {
+            color.painted = 0;
+        }
+        self.wrong_cells = 0;
+
+        for cell in self.cells.iter().flatten() {
+            if cell.is_wrong() {
+                self.wrong_cells += 1;
+            }
+            if let (true, Some(color)) = (cell.is_correct(), cell.color) {
+                self.palette.colors[color.index()].painted += 1;
+            }
+        }
+    }
+
+    // keeps the palette painted counts, which only count correctly painted
+    // cells, and the wrong cell count in sync with the cells
+    fn set_cell(&mut self, px: usize, py: usize, cell: Cell) {
+        let old = self.cells[py][px];
+        self.wrong_cells = self.wrong_cells - old.is_wrong() as usize + cell.is_wrong() as usize;
+        if let (true, Some(color)) = (old.is_correct(), old.color) {
+            self.palette.colors[color.index()].painted -= 1;
+        }
+        if let (true, Some(color)) = (cell.is_correct(), cell.color) {
+            self.palette.colors[color.index()].painted += 1;
+        }
+
+        self.cells[py][px] = cell;
+    }
+
+    fn change_cell(&mut self, px: usize, py: usize, painted: Option<ColorId>) {
+        let before = self.cells[py][px];
+        let after = Cell { painted, ..before };
+
+        self.set_cell(px, py, after);
+        self.history.record(CellChange {
+            x: px,
+            y: py,
+            before,
+            after,
+        });
+    }
+
+    // correctly painted cells are left alone, wrongly painted ones can be
+    // painted over
+    fn paint(&mut self, px: usize, py: usize) {
+        let cell = self.cells[py][px];
+        let color = self.palette.selected_color;
+        if cell.color.is_none()
+            || cell.is_correct()
+            || cell.painted == Some(color)
+            || cell.color != Some(color) && !self.allow_wrong
+        {
+            return;
+        }
+
+        if cell.color != Some(color) {
+            self.mistakes += 1;
+        }
+        self.change_cell(px, py, Some(color));
+    }
+
+    fn erase(&mut self, px: usize, py: usize) {
+        if self.cells[py][px].painted.is_some() {
+            self.change_cell(px, py, None);
+        }
+    }
+
+    // paints every unfilled pixel of the selected color connected to the
+    // given one
+    fn fill(&mut self, px: usize, py: usize) {
+        const STRAIGHT: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+        const DIAGONAL: [(isize, isize); 4] = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+
+        let neighbours = if self.fill_diagonal {
+            &[STRAIGHT, DIAGONAL][..]
+        } else {
+            &[STRAIGHT][..]
+        };
+        let color = Some(self.palette.selected_color);
+
+        let mut stack = vec![(px, py)];
+        while let Some((x, y)) = stack.pop() {
+            let cell = self.cells[y][x];
+            if cell.painted.is_some() || cell.color != color {
+                continue;
+            }
+
+            // painted pixels are skipped when they come up again
+            self.paint(x, y);
+
+            for &(dx, dy) in neighbours.iter().flatten() {
+                let (nx, ny) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+                if nx < self.width && ny < self.height {
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+
+    fn apply_tool(&mut self, tool: Tool, px: usize, py: usize) {
+        match tool {
+            Tool::Brush => self.paint(px, py),
+            Tool::Bucket => self.fill(px, py),
+            Tool::Eraser => self.erase(px, py),
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(stroke) = self.history.undo() {
+            for change in stroke.iter().rev() {
+                self.set_cell(change.x, change.y, change.before);
+            }
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(stroke) = self.history.redo() {
+            for change in &stroke {
+                self.set_cell(change.x, change.y, change.after);
+            }
+        }
+    }
+
+    pub fn update(&mut self, input: &Input, layout: &Layout) {
+        self.palette.update(input, layout);
+
+        // picking another color shows where it goes
+        let selected = Some(self.palette.selected_color);
+        if self.last_selected_color.is_some() && self.last_selected_color != selected {
+            self.highlight = true;
+        }
+        self.last_selected_color = selected;
+        if input.is_key_pressed(KeyCode::Char('H')) {
+            self.highlight = !self.highlight;
+        }
+
+        if input.is_key_pressed_with(KeyCode::Char('z'), KeyModifiers::CONTROL)
+            || input.is_key_pressed(KeyCode::Char('u'))
+        {
+            self.undo();
+        }
+        if input.is_key_pressed_with(KeyCode::Char('y'), KeyModifiers::CONTROL)
+            || input.is_key_pressed(KeyCode::Char('r'))
+        {
+            self.redo();
+        }
+
+        let moves = [
+            (KeyCode::Left, 'h', -1, 0),
+            (KeyCode::Down, 'j', 0, 1),
+            (KeyCode::Up, 'k', 0, -1),
+            (KeyCode::Right, 'l', 1, 0),
+        ];
+        for (code, key, dx, dy) in moves {
+            if input.is_key_pressed(code) || input.is_key_pressed(KeyCode::Char(key)) {
+                self.move_cursor(dx, dy, layout);
+            }
+        }
+        if input.is_key_pressed(KeyCode::Char('n')) {
+            self.find_remaining(false, layout);
+        }
+        if input.is_key_pressed(KeyCode::Char('N')) {
+            self.find_remaining(true, layout);
+        }
+        if input.is_key_pressed(KeyCode::Char(' ')) {
+            self.show_cursor = true;
+            self.apply_tool(self.tool, self.cursor.0, self.cursor.1);
+            self.history.end_stroke();
+        }
+
+        // tool keys switch back to the brush when pressed again
+        for (key, tool) in [('b', Tool::Bucket), ('e', Tool::Eraser)] {
+            if input.is_key_pressed(KeyCode::Char(key)) {
+                self.tool = if self.tool == tool { Tool::Brush } else { tool };
+            }
+        }
+        if input.is_key_pressed(KeyCode::Char('B')) {
+            self.fill_diagonal = !self.fill_diagonal;
+        }
 
         if let Some(mouse_drag) = &input.mouse_drag {
             if mouse_drag.button == MouseButton::Middle {
-                self.x += mouse_drag.offset_x as i16;
-                self.y += mouse_drag.offset_y as i16;
+                self.x += mouse_drag.offset_x as i32;
+                self.y += mouse_drag.offset_y as i32;
             }
         }
 
+        let (center_col, center_row) = layout.board_center();
+        if input.is_key_pressed(KeyCode::Char('+')) || input.is_key_pressed(KeyCode::Char('=')) {
+            self.zoom_at(center_col, center_row, 1);
+        }
+        if input.is_key_pressed(KeyCode::Char('-')) {
+            self.zoom_at(center_col, center_row, -1);
+        }
+        if input.is_key_pressed(KeyCode::Char('m')) {
+            let mode = match self.render_mode {
+                RenderMode::Blocks => RenderMode::HalfBlocks,
+                RenderMode::HalfBlocks => RenderMode::Blocks,
+            };
+            self.set_render_mode(mode, center_col, center_row);
+        }
+
+        // the palette and status line are drawn over the board and get
+        // their mouse events
         for frame_mouse in &input.frame_mouses {
-            if frame_mouse.active_button != Some(MouseButton::Left) {
+            let (x, y) = (frame_mouse.x as i16, frame_mouse.y as i16);
+            if layout.palette.contains(x, y) || layout.status.contains(x, y) {
+                self.drag_from = None;
                 continue;
             }
 
-            let px = (frame_mouse.x as i16 - self.x) / Self::CELL_WIDTH as i16;
-            let py = (frame_mouse.y as i16 - self.y) / Self::CELL_HEIGHT as i16;
+            if frame_mouse.scroll != 0 {
+                self.zoom_at(frame_mouse.x, frame_mouse.y, frame_mouse.scroll);
+            }
+
+            // the right button always erases
+            let (button, tool) = match frame_mouse.active_button {
+                Some(MouseButton::Left) => (MouseButton::Left, self.tool),
+                Some(MouseButton::Right) => (MouseButton::Right, Tool::Eraser),
+                _ => {
+                    self.drag_from = None;
+                    continue;
+                }
+            };
+
+            self.show_cursor = false;
+            let to = (frame_mouse.x, frame_mouse.y);
+            let from = self
+                .drag_from
+                .filter(|&(from_button, _)| from_button == button && !frame_mouse.pressed)
+                .map(|(_, from)| from);
 
-            if self.contains(px, py) {
-                let cell = &mut self.cells[py as usize][px as usize];
-                if !cell.filled && cell.color == self.palette.selected_color {
-                    cell.filled = true;
-                    self.palette.colors[self.palette.selected_color as usize].painted += 1;
+            // the bucket fills once per click rather than along a drag
+            let cells = match (tool, from) {
+                (Tool::Bucket, _) if !frame_mouse.pressed => continue,
+                (Tool::Bucket, _) | (_, None) => vec![to],
+                (_, Some(from)) => line(from, to),
+            };
+            self.drag_from = Some((button, to));
+
+            for (col, row) in cells {
+                let (xs, ys) = self.pixels_at(col, row);
+                for py in ys {
+                    for px in xs.clone() {
+                        if self.contains(px, py) {
+                            self.apply_tool(tool, px as usize, py as usize);
+                        }
+                    }
                 }
             }
         }
 
-        Ok(())
+        if !input.mouse_buttons_pressed.contains(&MouseButton::Left)
+            && !input.mouse_buttons_pressed.contains(&MouseButton::Right)
+        {
+            self.history.end_stroke();
+        }
     }
 
-    pub fn render(&self) -> io::Result<()> {
-        let size = terminal::size()?;
+    // whether the board needs frames without any input
+    pub fn animating(&self) -> bool {
+        self.blink
+            .is_some_and(|(_, start)| start.elapsed() < BLINK_DURATION)
+    }
 
-        let mut first_cx: Option<u16> = None;
+    fn is_highlighted(&self, cell: Cell) -> bool {
+        self.highlight && cell.painted.is_none() && cell.color == Some(self.palette.selected_color)
+    }
 
-        // TODO: intersect board rect with terminal rect
-        for px in 0..self.width() {
-            for py in 0..self.height() {
-                let cx = self.x + Self::CELL_WIDTH as i16 * px as i16;
-                let cy = self.y + Self::CELL_HEIGHT as i16 * py as i16;
+    fn highlight_color(&self) -> Color {
+        self.palette
+            .get_rgb(self.palette.selected_color)
+            .mix(HIGHLIGHT_GREY, HIGHLIGHT_GREY_AMOUNT)
+            .to_color()
+    }
 
-                if cx < 0
-                    || cx + Self::CELL_WIDTH as i16 >= size.0 as i16
-                    || cy < 0
-                    || cy + Self::CELL_HEIGHT as i16 - 1 >= size.1 as i16
-                {
-                    continue;
+    // the color a pixel is drawn with once highlighting is taken into account
+    fn shade(&self, cell: Cell, color: Color) -> Color {
+        if !self.highlight {
+            return color;
+        }
+        if self.is_highlighted(cell) {
+            return self.highlight_color();
+        }
+
+        match color {
+            Color::Rgb { r, g, b } => RGB::new(r, g, b).scale(DIM_BRIGHTNESS).to_color(),
+            _ => color,
+        }
+    }
+
+    // color of a painted pixel where there is no room to cross it out
+    fn painted_color(&self, cell: Cell, painted: ColorId) -> Color {
+        if cell.is_wrong() {
+            self.palette
+                .get_rgb(painted)
+                .mix(MISTAKE_TINT, MISTAKE_TINT_AMOUNT)
+                .to_color()
+        } else {
+            self.palette.get_color(painted)
+        }
+    }
+
+    // color of a pixel when zoomed out, unfilled pixels get a neutral
+    // color since there is no room for their number
+    fn sample(&self, px: i32, py: i32) -> Option<Color> {
+        if !self.contains(px, py) {
+            return None;
+        }
+
+        let cell = self.get(px as usize, py as usize);
+        let color = match (cell.painted, cell.color) {
+            (_, None) => return None,
+            (Some(painted), _) => self.painted_color(cell, painted),
+            (None, Some(_)) => UNFILLED_COLOR,
+        };
+        Some(self.shade(cell, color))
+    }
+
+    // zoomed out, each terminal cell shows up to 2x2 pixels with quadrant
+    // characters, using the two most common colors when there are more
+    fn render_zoomed_out(&self, screen: &mut Screen) {
+        let size = screen.size();
+        let (w, h) = self.cell_size();
+        let right = self.x + (self.width as i32 * w as i32 + 1) / 2;
+        let bottom = self.y + (self.height as i32 * h as i32 + 1) / 2;
+
+        for row in (self.y).max(0)..bottom.min(size.1 as i32) {
+            for col in (self.x).max(0)..right.min(size.0 as i32) {
+                let hx = 2 * (col - self.x);
+                let hy = 2 * (row - self.y);
+
+                // top left, top right, bottom left, bottom right
+                let quadrants = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| {
+                    self.sample(
+                        (hx + dx).div_euclid(w as i32),
+                        (hy + dy).div_euclid(h as i32),
+                    )
+                });
+
+                let mut counts: Vec<(Option<Color>, usize)> = Vec::new();
+                for quadrant in quadrants {
+                    match counts.iter_mut().find(|(c, _)| *c == quadrant) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((quadrant, 1)),
+                    }
                 }
+                counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+                // the terminal background can't be used as a foreground
+                // color, so it always goes in the background
+                let top = counts.iter().take(2).map(|&(c, _)| c).collect::<Vec<_>>();
+                let Some(&fg) = top.iter().flatten().next() else {
+                    continue;
+                };
+                let bg = top.iter().copied().find(|&c| c != Some(fg)).flatten();
 
-                let cx = cx as u16;
-                let cy = cy as u16;
+                let mask = quadrants
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &q)| q == Some(fg) || q.is_some() && q != bg)
+                    .fold(0, |mask, (i, _)| mask | 1 << i);
 
-                if first_cx.is_none() {
-                    first_cx = Some(cx);
+                screen.print(
+                    col,
+                    row,
+                    QUADRANTS[mask],
+                    Style::new(fg, bg.unwrap_or(Color::Reset)),
+                );
+            }
+        }
+    }
+
+    // color of the top or bottom half of a terminal cell, unfilled pixels
+    // are a dimmed version of the color they expect
+    fn half_block_color(&self, px: i32, py: i32) -> Option<Color> {
+        if !self.contains(px, py) {
+            return None;
+        }
+
+        let cell = self.get(px as usize, py as usize);
+        let color = match (cell.painted, cell.color) {
+            (_, None) => return None,
+            (Some(painted), _) => self.painted_color(cell, painted),
+            (None, Some(color)) => self
+                .palette
+                .get_rgb(color)
+                .scale(UNFILLED_BRIGHTNESS)
+                .to_color(),
+        };
+        Some(self.shade(cell, color))
+    }
+
+    // number shown in a terminal cell of an unfilled pixel, centered in
+    // the terminal cells the pixel fully covers
+    fn half_block_hint(&self, px: i32, py: i32, col: i32, row: i32) -> Option<(String, usize)> {
+        let cell = self.get(px as usize, py as usize);
+        let (None, Some(color)) = (cell.painted, cell.color) else {
+            return None;
+        };
+
+        let (w, h) = self.cell_size();
+        let cols = (w / 2) as i32;
+        let label = self.palette.label(color, self.label_width())?;
+        if label.len() as i32 > cols {
+            return None;
+        }
+
+        // terminal rows fully covered by the pixel, relative to the board
+        let first_row = (py * h as i32 + 1) / 2;
+        let last_row = ((py + 1) * h as i32) / 2 - 1;
+        if last_row < first_row || row - self.y != (first_row + last_row) / 2 {
+            return None;
+        }
+
+        let start = px * cols + (cols - label.len() as i32) / 2;
+        let offset = col - self.x - start;
+        (0..label.len() as i32)
+            .contains(&offset)
+            .then_some((label, offset as usize))
+    }
+
+    fn render_half_blocks(&self, screen: &mut Screen) {
+        let size = screen.size();
+        let (w, h) = self.cell_size();
+        let cols = (w / 2) as i32;
+        let right = self.x + self.width as i32 * cols;
+        let bottom = self.y + (self.height as i32 * h as i32 + 1) / 2;
+
+        for row in (self.y).max(0)..bottom.min(size.1 as i32) {
+            for col in (self.x).max(0)..right.min(size.0 as i32) {
+                let px = (col - self.x) / cols;
+                let hy = 2 * (row - self.y);
+                let (top, bottom) = (hy / h as i32, (hy + 1) / h as i32);
+
+                let top_color = self.half_block_color(px, top);
+                let bottom_color = self.half_block_color(px, bottom);
+
+                if top == bottom {
+                    if let Some((label, offset)) = self.half_block_hint(px, top, col, row) {
+                        let cell = self.get(px as usize, top as usize);
+                        let bg = top_color.unwrap_or(Color::Reset);
+                        let hint_color = if self.is_highlighted(cell) {
+                            contrast(bg)
+                        } else {
+                            cell.color.map_or(Color::Reset, |color| {
+                                let hint = self.palette.get_rgb(color).scale(HINT_BRIGHTNESS);
+                                self.shade(cell, hint.to_color())
+                            })
+                        };
+
+                        screen.print(
+                            col,
+                            row,
+                            &label[offset..offset + 1],
+                            Style::new(hint_color, bg),
+                        );
+                        continue;
+                    }
                 }
 
-                let cell = self.get(px, py);
-                let mut stdout = io::stdout();
-
-                if cell.filled {
-                    queue!(
-                        stdout,
-                        cursor::MoveTo(cx, cy),
-                        style::SetBackgroundColor(self.palette.get_color(cell.color)),
-                        style::Print("  "),
-                        style::ResetColor
-                    )?;
-                // TODO: shift palette
-                } else if cell.color < 9 {
-                    queue!(
-                        stdout,
-                        cursor::MoveTo(cx, cy),
-                        style::Print(CELL_NUMBERS[cell.color as usize]),
-                    )?;
+                let (glyph, fg, bg) = match (top_color, bottom_color) {
+                    (None, None) => continue,
+                    (Some(top), bottom) => ("▀", top, bottom),
+                    (None, Some(bottom)) => ("▄", bottom, None),
+                };
+
+                screen.print(col, row, glyph, Style::new(fg, bg.unwrap_or(Color::Reset)));
+            }
+        }
+    }
+
+    // brackets around the cursor pixel, or next to it when it is a single
+    // terminal cell wide, over whatever the board drew there
+    fn render_cursor(&self, screen: &mut Screen) {
+        let (cols, rows) = self.pixel_rect(self.cursor.0, self.cursor.1);
+        let row = rows.start + (rows.len() as i32 - 1) / 2;
+
+        let (left, right) = if cols.len() < 2 {
+            (cols.start - 1, cols.start + 1)
+        } else {
+            (cols.start, cols.end - 1)
+        };
+
+        for (col, glyph) in [(left, "["), (right, "]")] {
+            let Some(under) = screen.get(col, row) else {
+                continue;
+            };
+
+            let bg = under.style.bg;
+            screen.print(col, row, glyph, Style::new(contrast(bg), bg).bold());
+        }
+    }
+
+    // flashes the pixel jumped to, keeping whatever is drawn on it readable
+    fn render_blink(&self, screen: &mut Screen) {
+        let Some(((px, py), start)) = self.blink else {
+            return;
+        };
+        let elapsed = start.elapsed();
+        if elapsed >= BLINK_DURATION || (elapsed.as_millis() / BLINK_PERIOD.as_millis()) % 2 == 1 {
+            return;
+        }
+
+        let (cols, rows) = self.pixel_rect(px, py);
+        for row in rows {
+            for col in cols.clone() {
+                let Some(under) = screen.get(col, row) else {
+                    continue;
+                };
+
+                let bg = contrast(under.style.bg);
+                screen.print(
+                    col,
+                    row,
+                    &under.glyph.to_string(),
+                    Style::new(contrast(bg), bg),
+                );
+            }
+        }
+    }
+
+    pub fn render_status(&self, screen: &mut Screen, layout: &Layout) {
+        let tool = match (self.tool, self.fill_diagonal) {
+            (Tool::Brush, _) => "brush",
+            (Tool::Bucket, false) => "bucket, 4-connected",
+            (Tool::Bucket, true) => "bucket, 8-connected",
+            (Tool::Eraser, _) => "eraser",
+        };
+
+        let mut text = format!("tool: {tool}");
+        if self.allow_wrong {
+            text.push_str(&format!("   mistakes: {}", self.mistakes));
+            if self.wrong_cells > 0 {
+                text.push_str(&format!(", {} left to fix", self.wrong_cells));
+            }
+        }
+
+        let status = &layout.status;
+        screen.print(
+            status.x as i32 + 1,
+            status.y as i32,
+            &text,
+            Style::fg(Color::DarkGrey),
+        );
+    }
+
+    pub fn render(&self, screen: &mut Screen) {
+        if self.render_mode == RenderMode::HalfBlocks {
+            self.render_half_blocks(screen);
+        } else if self.cell_size().0 < 4 {
+            self.render_zoomed_out(screen);
+        } else {
+            self.render_blocks(screen);
+        }
+
+        self.render_blink(screen);
+        if self.show_cursor {
+            self.render_cursor(screen);
+        }
+    }
+
+    fn render_blocks(&self, screen: &mut Screen) {
+        let size = screen.size();
+        let (w, h) = self.cell_size();
+
+        // pixel size in terminal cells
+        let cols = (w / 2) as i32;
+        let rows = (h / 2) as i32;
+        let label_row = (rows - 1) / 2;
+        let label_width = self.label_width();
+
+        let (x, y) = (self.x, self.y);
+        let screen_width = size.0 as i32;
+
+        // board rect intersected with the terminal rect, in pixels
+        let first_px = ((-x).max(0) / cols) as usize;
+        let last_px = ((screen_width - x + cols - 1) / cols).clamp(0, self.width as i32) as usize;
+        if first_px >= last_px {
+            return;
+        }
+
+        let top = y.max(0);
+        let bottom = (y + self.height as i32 * rows).min(size.1 as i32);
+
+        for row in top..bottom {
+            let cells = &self.cells[((row - y) / rows) as usize];
+            let on_label_row = (row - y) % rows == label_row;
+
+            let mut px = first_px;
+            while px < last_px {
+                let start = px;
+                px += 1;
+
+                let cell = cells[start];
+                let Some(color) = cell.color else {
+                    continue;
+                };
+
+                if let Some(painted) = cell.painted {
+                    let wrong = cell.is_wrong();
+                    while px < last_px
+                        && cells[px].painted == Some(painted)
+                        && cells[px].is_wrong() == wrong
+                    {
+                        px += 1;
+                    }
+
+                    let (glyph, style) = if wrong {
+                        let bg = self.shade(cell, self.palette.get_rgb(painted).to_color());
+                        (MISTAKE_GLYPH, Style::new(contrast(bg), bg))
+                    } else {
+                        (
+                            " ",
+                            Style::bg(self.shade(cell, self.palette.get_color(painted))),
+                        )
+                    };
+
+                    // the screen clips the parts of the run hanging off it
+                    screen.print(
+                        x + start as i32 * cols,
+                        row,
+                        &glyph.repeat((px - start) * cols as usize),
+                        style,
+                    );
+                } else {
+                    let pixel_x = x + start as i32 * cols;
+                    let style = if self.is_highlighted(cell) {
+                        let bg = self.highlight_color();
+                        screen.print(pixel_x, row, &" ".repeat(cols as usize), Style::bg(bg));
+                        Style::new(contrast(bg), bg)
+                    } else if self.highlight {
+                        Style::fg(Color::DarkGrey)
+                    } else {
+                        Style::default()
+                    };
+
+                    if !on_label_row {
+                        continue;
+                    }
+                    let Some(label) = self.palette.label(color, label_width) else {
+                        continue;
+                    };
+
+                    let label_x = pixel_x + (cols - label.len() as i32) / 2;
+                    screen.print(label_x, row, &label, style);
                 }
             }
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(())
+    #[test]
+    fn too_many_colors() {
+        let width = BoardData::MAX_COLORS + 1;
+        let pixels = (0..width)
+            .map(|i| Some(RGB::new(i as u8, (i >> 8) as u8, (i >> 16) as u8)))
+            .collect();
+
+        match BoardData::from_pixels(width, 1, pixels) {
+            Err(BoardLoadError::TooManyColors { x, y, max }) => {
+                assert_eq!(
+                    (x, y, max),
+                    (BoardData::MAX_COLORS, 0, BoardData::MAX_COLORS)
+                );
+            }
+            Err(e) => panic!("expected too many colors, got {e:?}"),
+            Ok(_) => panic!("expected too many colors"),
+        }
+    }
+
+    #[test]
+    fn no_opaque_pixels() {
+        match BoardData::from_pixels(2, 2, vec![None; 4]) {
+            Err(BoardLoadError::Empty) => {}
+            Err(e) => panic!("expected an empty image, got {e:?}"),
+            Ok(_) => panic!("expected an empty image"),
+        }
     }
 }
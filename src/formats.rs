@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use crate::{
+    board::{BoardLoadError, ImageData},
+    netpbm,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Netpbm,
+    Png,
+    Gif,
+    Bmp,
+    Qoi,
+}
+
+impl ImageFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Netpbm => "Netpbm",
+            Self::Png => "PNG",
+            Self::Gif => "GIF",
+            Self::Bmp => "BMP",
+            Self::Qoi => "QOI",
+        }
+    }
+
+    fn from_magic(data: &[u8]) -> Option<Self> {
+        match data {
+            [b'P', b'1'..=b'7', ..] => Some(Self::Netpbm),
+            [0x89, b'P', b'N', b'G', ..] => Some(Self::Png),
+            [b'G', b'I', b'F', b'8', ..] => Some(Self::Gif),
+            [b'B', b'M', ..] => Some(Self::Bmp),
+            [b'q', b'o', b'i', b'f', ..] => Some(Self::Qoi),
+            _ => None,
+        }
+    }
+
+    fn from_extension(file_path: &str) -> Option<Self> {
+        let extension = Path::new(file_path)
+            .extension()?
+            .to_str()?
+            .to_ascii_lowercase();
+
+        match extension.as_str() {
+            "pbm" | "pgm" | "ppm" | "pnm" | "pam" => Some(Self::Netpbm),
+            "png" => Some(Self::Png),
+            "gif" => Some(Self::Gif),
+            "bmp" => Some(Self::Bmp),
+            "qoi" => Some(Self::Qoi),
+            _ => None,
+        }
+    }
+
+    // magic bytes win over the extension, which is only a fallback
+    pub fn detect(file_path: &str, data: &[u8]) -> Option<Self> {
+        Self::from_magic(data).or_else(|| Self::from_extension(file_path))
+    }
+}
+
+#[cfg(feature = "decoders")]
+fn decode_with_image(format: ImageFormat, data: &[u8]) -> Result<ImageData, BoardLoadError> {
+    use crate::palette::RGB;
+
+    let name = format.name();
+    let format = match format {
+        ImageFormat::Netpbm => image::ImageFormat::Pnm,
+        ImageFormat::Png => image::ImageFormat::Png,
+        ImageFormat::Gif => image::ImageFormat::Gif,
+        ImageFormat::Bmp => image::ImageFormat::Bmp,
+        ImageFormat::Qoi => image::ImageFormat::Qoi,
+    };
+
+    let decoded = image::load_from_memory_with_format(data, format)
+        .map_err(|e| BoardLoadError::Decode {
+            format: name,
+            message: e.to_string(),
+        })?
+        .into_rgba8();
+
+    let pixels = decoded
+        .pixels()
+        .map(|&image::Rgba([r, g, b, a])| {
+            // mostly transparent pixels are not part of the puzzle
+            (a >= 128).then_some(RGB { r, g, b })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ImageData {
+        width: decoded.width() as usize,
+        height: decoded.height() as usize,
+        pixels,
+    })
+}
+
+#[cfg(not(feature = "decoders"))]
+fn decode_with_image(format: ImageFormat, _data: &[u8]) -> Result<ImageData, BoardLoadError> {
+    Err(BoardLoadError::UnsupportedFormat(format.name()))
+}
+
+pub fn decode(file_path: &str, data: &[u8]) -> Result<ImageData, BoardLoadError> {
+    match ImageFormat::detect(file_path, data) {
+        Some(ImageFormat::Netpbm) => netpbm::decode(data),
+        Some(format) => decode_with_image(format, data),
+        None => Err(BoardLoadError::UnknownFormat),
+    }
+}
@@ -0,0 +1,116 @@
+// puts the terminal back the way it was found however the game ends, by
+// returning, by panicking or by being sent a signal
+
+use std::{
+    io, panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+#[cfg(unix)]
+use std::{process, thread};
+
+use crossterm::{
+    cursor,
+    event::{self, KeyboardEnhancementFlags},
+    execute, terminal,
+};
+use signal_hook::consts::signal;
+#[cfg(not(unix))]
+use signal_hook::flag;
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
+
+// whether the terminal is currently set up for the game, so restoring twice
+// (from the panic hook and then the guard) is harmless
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    pub fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        ACTIVE.store(true, Ordering::SeqCst);
+
+        // from here on a failure drops the guard, undoing what was done
+        let guard = Self;
+        execute!(
+            io::stdout(),
+            terminal::EnterAlternateScreen,
+            event::EnableMouseCapture,
+            event::PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES),
+            cursor::Hide
+        )?;
+
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// errors are ignored, there is nothing left to do about them and the rest
+// of the steps should still be tried
+pub fn restore() {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return;
+    }
+
+    let _ = execute!(
+        io::stdout(),
+        event::DisableMouseCapture,
+        event::PopKeyboardEnhancementFlags,
+        terminal::LeaveAlternateScreen,
+        cursor::Show
+    );
+    let _ = terminal::disable_raw_mode();
+}
+
+// restores the terminal before the panic message is printed, otherwise it
+// ends up on the alternate screen and is lost
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        default_hook(info);
+    }));
+}
+
+// the returned flag is set on SIGTERM, SIGHUP or SIGINT so the game can quit
+// normally and save. a second signal means the game is stuck somewhere it
+// doesn't look at the flag, so the terminal is restored and the process
+// exits right away
+#[cfg(unix)]
+pub fn register_signals() -> io::Result<Arc<AtomicBool>> {
+    let quit = Arc::new(AtomicBool::new(false));
+    let mut signals = Signals::new([signal::SIGTERM, signal::SIGINT, signal::SIGHUP])?;
+
+    let flag = Arc::clone(&quit);
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            if flag.swap(true, Ordering::SeqCst) {
+                restore();
+                process::exit(1);
+            }
+        }
+    });
+
+    Ok(quit)
+}
+
+// there is no signal iterator to run the second signal handling from, and
+// exiting straight from a handler would skip restoring the terminal
+#[cfg(not(unix))]
+pub fn register_signals() -> io::Result<Arc<AtomicBool>> {
+    let quit = Arc::new(AtomicBool::new(false));
+
+    for signal in [signal::SIGTERM, signal::SIGINT] {
+        flag::register(signal, Arc::clone(&quit))?;
+    }
+
+    Ok(quit)
+}
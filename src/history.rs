@@ -0,0 +1,57 @@
+use crate::board::Cell;
+
+// keep memory bounded on very long sessions
+const MAX_STROKES: usize = 1000;
+
+#[derive(Clone, Copy)]
+pub struct CellChange {
+    pub x: usize,
+    pub y: usize,
+    pub before: Cell,
+    pub after: Cell,
+}
+
+// a stroke is every change made between pressing and releasing the mouse,
+// and is undone and redone as a whole
+pub type Stroke = Vec<CellChange>;
+
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Stroke>,
+    redo: Vec<Stroke>,
+    stroke: Stroke,
+}
+
+impl History {
+    pub fn record(&mut self, change: CellChange) {
+        self.stroke.push(change);
+    }
+
+    pub fn end_stroke(&mut self) {
+        if self.stroke.is_empty() {
+            return;
+        }
+
+        self.undo.push(std::mem::take(&mut self.stroke));
+        if self.undo.len() > MAX_STROKES {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self) -> Option<Stroke> {
+        self.end_stroke();
+
+        let stroke = self.undo.pop()?;
+        self.redo.push(stroke.clone());
+        Some(stroke)
+    }
+
+    pub fn redo(&mut self) -> Option<Stroke> {
+        self.end_stroke();
+
+        let stroke = self.redo.pop()?;
+        self.undo.push(stroke.clone());
+        Some(stroke)
+    }
+}
@@ -1,8 +1,17 @@
-use std::{collections::HashSet, io, time::Duration};
+use std::{
+    collections::HashSet,
+    io::{self, IsTerminal},
+    time::Duration,
+};
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, MouseButton, MouseEventKind};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind,
+};
 
 pub struct Input {
+    // terminal size, kept up to date from resize events
+    pub size: (u16, u16),
+
     pub mouse_x: u16,
     pub mouse_y: u16,
 
@@ -12,6 +21,8 @@ pub struct Input {
     drag_start_y: u16,
 
     pub keys_pressed: HashSet<KeyCode>,
+    // key presses and repeats received this frame
+    pub frame_keys: Vec<KeyEvent>,
     pub mouse_buttons_pressed: HashSet<MouseButton>,
     pub mouse_drag: Option<MouseDrag>,
 }
@@ -20,6 +31,10 @@ pub struct FrameMouse {
     pub x: u16,
     pub y: u16,
     pub active_button: Option<MouseButton>,
+    // the button went down on this event rather than being dragged
+    pub pressed: bool,
+    // 1 when scrolling up, -1 when scrolling down
+    pub scroll: i16,
 }
 
 pub struct MouseDrag {
@@ -44,9 +59,48 @@ impl Rect {
     }
 }
 
+// crossterm keeps reading a terminal that hung up and spins forever inside
+// `event::poll`, so the waiting is done here and ends with an error once the
+// terminal is gone, letting the game save and quit
+#[cfg(unix)]
+fn wait_for_input(timeout: Duration) -> io::Result<bool> {
+    if !io::stdin().is_terminal() {
+        return event::poll(timeout);
+    }
+
+    let mut stdin = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+    // a signal landing on this thread, a resize included, cuts the wait
+    // short, one landing on another thread waits for the timeout. crossterm
+    // picks up resizes below either way
+    // SAFETY: `stdin` is a valid pollfd that outlives the call, and the count
+    // of 1 matches it
+    let ready = unsafe { libc::poll(&mut stdin, 1, timeout) };
+    if ready > 0 && stdin.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::BrokenPipe,
+            "The terminal was closed",
+        ));
+    }
+
+    event::poll(Duration::ZERO)
+}
+
+#[cfg(not(unix))]
+fn wait_for_input(timeout: Duration) -> io::Result<bool> {
+    event::poll(timeout)
+}
+
 impl Input {
-    pub fn new() -> Self {
+    pub fn new(size: (u16, u16)) -> Self {
         Self {
+            size,
+
             mouse_x: 0,
             mouse_y: 0,
 
@@ -56,33 +110,46 @@ impl Input {
             drag_start_y: 0,
 
             keys_pressed: HashSet::new(),
+            frame_keys: Vec::new(),
             mouse_buttons_pressed: HashSet::new(),
             mouse_drag: None,
         }
     }
 
-    pub fn process_events(&mut self) -> io::Result<()> {
+    // waits up to `timeout` for input, forever when it is None, then
+    // handles everything that arrived, returns false when nothing did
+    pub fn process_events(&mut self, timeout: Option<Duration>) -> io::Result<bool> {
         // clear drag state and frame mouse positions
         self.mouse_drag = None;
         // we need to keep track of multiple mouse positions
         // per frame because we can receive multiple mouse events
         self.frame_mouses.clear();
+        self.frame_keys.clear();
 
         // store drag start position for current frame for the
         // same reason as above
         let mut frame_drag_start_x = self.drag_start_x;
         let mut frame_drag_start_y = self.drag_start_y;
 
-        while event::poll(Duration::ZERO)? {
+        let received = match timeout {
+            Some(timeout) => wait_for_input(timeout)?,
+            None => true,
+        };
+
+        let mut pending = received;
+        while pending {
             match event::read()? {
                 Event::Key(e) => match e.kind {
                     KeyEventKind::Press => {
                         self.keys_pressed.insert(e.code);
+                        self.frame_keys.push(e);
+                    }
+                    KeyEventKind::Repeat => {
+                        self.frame_keys.push(e);
                     }
                     KeyEventKind::Release => {
                         self.keys_pressed.remove(&e.code);
                     }
-                    _ => {}
                 },
                 Event::Mouse(e) => {
                     self.mouse_x = e.column;
@@ -92,6 +159,8 @@ impl Input {
                         x: self.mouse_x,
                         y: self.mouse_y,
                         active_button: None,
+                        pressed: false,
+                        scroll: 0,
                     };
 
                     match e.kind {
@@ -107,6 +176,7 @@ impl Input {
                             }
 
                             frame_mouse.active_button = Some(b);
+                            frame_mouse.pressed = true;
                         }
                         MouseEventKind::Up(b) => {
                             self.mouse_buttons_pressed.remove(&b);
@@ -125,22 +195,47 @@ impl Input {
 
                             frame_mouse.active_button = Some(b);
                         }
+                        MouseEventKind::ScrollUp => frame_mouse.scroll = 1,
+                        MouseEventKind::ScrollDown => frame_mouse.scroll = -1,
                         _ => {}
                     };
 
                     self.frame_mouses.push(frame_mouse);
                 }
+                Event::Resize(width, height) => {
+                    self.size = (width, height);
+                }
                 _ => {}
             }
+
+            pending = event::poll(Duration::ZERO)?;
         }
 
-        Ok(())
+        Ok(received)
     }
 
     pub fn is_key_down(&self, key: KeyCode) -> bool {
         self.keys_pressed.contains(&key)
     }
 
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.frame_keys
+            .iter()
+            .any(|e| e.code == key && (e.modifiers - KeyModifiers::SHIFT).is_empty())
+    }
+
+    pub fn is_key_pressed_with(&self, key: KeyCode, modifiers: KeyModifiers) -> bool {
+        self.frame_keys
+            .iter()
+            .any(|e| e.code == key && e.modifiers == modifiers)
+    }
+
+    pub fn is_mouse_clicked_in(&self, button: MouseButton, rect: &Rect) -> bool {
+        self.frame_mouses.iter().any(|m| {
+            m.pressed && m.active_button == Some(button) && rect.contains(m.x as i16, m.y as i16)
+        })
+    }
+
     pub fn is_mouse_button_down_in(&self, button: MouseButton, rect: Rect) -> bool {
         self.mouse_buttons_pressed.contains(&button)
             && rect.contains(self.mouse_x as i16, self.mouse_y as i16)
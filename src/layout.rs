@@ -0,0 +1,75 @@
+// where everything goes on screen, worked out once per frame from the
+// terminal size so no drawing code does its own unsigned math on it
+
+use crate::{
+    input::Rect,
+    palette::{PALETTE_HEIGHT, PALETTE_WIDTH},
+    screen::{Screen, Style},
+};
+
+const STATUS_HEIGHT: u16 = 1;
+
+// status line and palette with a few rows of board between them
+pub const MIN_WIDTH: u16 = PALETTE_WIDTH;
+pub const MIN_HEIGHT: u16 = STATUS_HEIGHT + PALETTE_HEIGHT + 3;
+
+pub struct Layout {
+    pub size: (u16, u16),
+    // line at the top of the screen showing the current tool
+    pub status: Rect,
+    // part of the screen between the status line and the palette, the board may still be drawn
+    // under the palette but is kept centered in here
+    pub board: Rect,
+    pub palette: Rect,
+    pub too_small: bool,
+}
+
+impl Layout {
+    pub fn new(size: (u16, u16)) -> Self {
+        let palette = Rect {
+            x: (size.0.saturating_sub(PALETTE_WIDTH) / 2) as i16,
+            y: size.1.saturating_sub(PALETTE_HEIGHT) as i16,
+            width: PALETTE_WIDTH,
+            height: PALETTE_HEIGHT,
+        };
+
+        Self {
+            size,
+            status: Rect {
+                x: 0,
+                y: 0,
+                width: size.0,
+                height: STATUS_HEIGHT.min(size.1),
+            },
+            board: Rect {
+                x: 0,
+                y: STATUS_HEIGHT as i16,
+                width: size.0,
+                height: size.1.saturating_sub(STATUS_HEIGHT + PALETTE_HEIGHT),
+            },
+            palette,
+            too_small: size.0 < MIN_WIDTH || size.1 < MIN_HEIGHT,
+        }
+    }
+
+    pub fn board_center(&self) -> (u16, u16) {
+        (
+            self.board.x as u16 + self.board.width / 2,
+            self.board.y as u16 + self.board.height / 2,
+        )
+    }
+
+    pub fn render_too_small(&self, screen: &mut Screen) {
+        let lines = [
+            "Terminal too small".to_string(),
+            format!("{}x{}", self.size.0, self.size.1),
+            format!("needs {MIN_WIDTH}x{MIN_HEIGHT}"),
+        ];
+
+        let top = (self.size.1 as i32 - lines.len() as i32) / 2;
+        for (i, line) in lines.iter().enumerate() {
+            let x = (self.size.0 as i32 - line.len() as i32) / 2;
+            screen.print(x.max(0), top.max(0) + i as i32, line, Style::default());
+        }
+    }
+}
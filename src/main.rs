@@ -1,84 +1,256 @@
 use std::{
-    io::{self, Write},
+    env, io,
+    process::ExitCode,
+    sync::atomic::{AtomicBool, Ordering},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use board::BoardData;
+use args::{Args, Command};
+use board::{BoardData, BoardLoadError, RenderMode};
 use crossterm::{
-    cursor,
-    event::{self, KeyCode, KeyboardEnhancementFlags},
-    execute, queue,
-    terminal::{self, ClearType},
+    event::{KeyCode, KeyModifiers},
+    terminal,
 };
 
-use crate::{board::Board, input::Input};
+use crate::{
+    board::Board, guard::TerminalGuard, input::Input, layout::Layout, save::SaveFile,
+    screen::Screen,
+};
 
+mod args;
 mod board;
+mod formats;
+mod guard;
+mod history;
 mod input;
+mod layout;
+mod netpbm;
 mod palette;
+mod quantize;
+mod resample;
+mod save;
+mod screen;
 
-const FPS: u64 = 60;
+// how often a blocked loop wakes up to look for signals and resizes, which
+// only cut the wait for input short when they land on the game's thread
+// rather than the signal handling one
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(250);
 
-fn main() -> io::Result<()> {
+fn game_loop(
+    board: &mut Board,
+    args: &Args,
+    save_file: Option<&SaveFile>,
+    quit_signal: &AtomicBool,
+) -> io::Result<()> {
     let mut stdout = io::stdout();
+    let mut input = Input::new(terminal::size()?);
+    let mut layout = Layout::new(input.size);
+    let mut screen = Screen::new(layout.size);
+    let frame_time = Duration::from_millis(1000 / args.fps);
+    let autosave_interval = Duration::from_secs(args.autosave);
+    let mut last_save = Instant::now();
 
-    terminal::enable_raw_mode()?;
-    execute!(
-        stdout,
-        terminal::EnterAlternateScreen,
-        event::EnableMouseCapture,
-        event::PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES),
-        cursor::Hide
-    )?;
-
-    let board_data = BoardData::from_ppm_file("images/test.ppm")?;
-    let mut board = Board::new(board_data);
-
-    let mut input = Input::new();
-
+    // the first frame is drawn before any input arrives
+    let mut redraw = true;
     let mut quit = false;
     while !quit {
-        let start = std::time::Instant::now();
+        // sleep until there is input, or until the next animation frame or
+        // autosave is due
+        let mut timeout = SIGNAL_CHECK_INTERVAL;
+        if board.animating() {
+            timeout = timeout.min(frame_time);
+        }
+        if save_file.is_some() && args.autosave > 0 {
+            timeout = timeout.min(autosave_interval.saturating_sub(last_save.elapsed()));
+        }
 
         // begin update
-        input.process_events()?;
+        redraw |= input.process_events(Some(timeout))?;
 
-        if input.is_key_down(KeyCode::Char('q')) {
+        if input.size != layout.size {
+            let new_layout = Layout::new(input.size);
+            board.resize(&layout, &new_layout);
+            layout = new_layout;
+        }
+
+        // raw mode turns ctrl+c into a key press instead of a signal
+        if input.is_key_down(KeyCode::Char('q'))
+            || input.is_key_pressed_with(KeyCode::Char('c'), KeyModifiers::CONTROL)
+            || quit_signal.load(Ordering::Relaxed)
+        {
             quit = true;
         }
 
-        board.update(&input)?;
+        if !layout.too_small {
+            board.update(&input, &layout);
+        }
+
+        if let Some(save_file) = save_file {
+            if args.autosave > 0 && last_save.elapsed() >= autosave_interval {
+                // a failed autosave is retried on the next interval, and
+                // reported if it still fails when quitting
+                let _ = save_file.store(board);
+                last_save = Instant::now();
+            }
+        }
         // end update
 
+        if !(redraw || board.animating()) {
+            continue;
+        }
+        // one more frame after an animation ends so its last state is
+        // cleared
+        redraw = board.animating();
+
         // render
-        queue!(
-            stdout,
-            terminal::BeginSynchronizedUpdate,
-            terminal::Clear(ClearType::All)
-        )?;
+        let start = Instant::now();
+
+        if layout.size != screen.size() {
+            screen.resize(layout.size);
+        }
+        screen.clear();
 
-        board.render()?;
-        board.palette.render()?;
+        if layout.too_small {
+            layout.render_too_small(&mut screen);
+        } else {
+            board.render(&mut screen);
+            board.render_status(&mut screen, &layout);
+            board
+                .palette
+                .render(&mut screen, &layout, board.label_width());
+        }
 
-        queue!(stdout, terminal::EndSynchronizedUpdate)?;
-        stdout.flush()?;
+        // frames where nothing changed write nothing
+        screen.flush(&mut stdout)?;
         // end render
 
+        // input arriving faster than the frame rate is handled in one go
+        // on the next frame
         let elapsed = start.elapsed();
-        if elapsed < Duration::from_millis(1000 / FPS) {
-            thread::sleep(Duration::from_millis(1000 / FPS) - elapsed);
+        if elapsed < frame_time {
+            thread::sleep(frame_time - elapsed);
         }
     }
 
-    execute!(
-        stdout,
-        event::DisableMouseCapture,
-        event::PopKeyboardEnhancementFlags,
-        terminal::LeaveAlternateScreen,
-        cursor::Show
-    )?;
-    terminal::disable_raw_mode()?;
-
     Ok(())
 }
+
+fn run(board: &mut Board, args: &Args, save_file: Option<&SaveFile>) -> ExitCode {
+    let result = guard::register_signals().and_then(|quit_signal| {
+        // restores the terminal when dropped, even if the game loop failed
+        let _guard = TerminalGuard::enter()?;
+
+        game_loop(board, args, save_file, &quit_signal)
+    });
+
+    // saved whatever the game loop returned, it fails when the terminal is
+    // gone, which is also when progress since the last autosave is at stake.
+    // printing to a terminal that is gone panics, so that comes after
+    let saved = save_file.map_or(Ok(()), |save_file| save_file.store(board));
+
+    let mut code = ExitCode::SUCCESS;
+    if let Err(e) = result {
+        eprintln!("error: {e}");
+        code = ExitCode::FAILURE;
+    }
+    if let (Some(save_file), Err(e)) = (save_file, saved) {
+        eprintln!(
+            "error: could not save progress to '{}': {e}",
+            save_file.path.display()
+        );
+        code = ExitCode::FAILURE;
+    }
+
+    code
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(Command::Run(args)) => args,
+        Ok(Command::Help) => {
+            println!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("pixel-tui {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{}", args::USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // load everything before touching the terminal so errors are
+    // printed on a normal screen
+    let board_data = match BoardData::from_file(&args.image_path, &args.load_options()) {
+        Ok(board_data) => board_data,
+        Err(e) => {
+            eprintln!("error: could not load '{}': {e}", args.image_path);
+            if let BoardLoadError::TooManyColors { .. } = e {
+                eprintln!("hint: use --colors to reduce the number of colors");
+            }
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Some(color) = args.color {
+        if color.index() >= board_data.colors.len() {
+            eprintln!(
+                "error: --color {} is out of range, '{}' only has {} colors",
+                color.index() + 1,
+                args.image_path,
+                board_data.colors.len()
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let save_file = if args.save {
+        SaveFile::for_puzzle(save::puzzle_hash(&board_data))
+    } else {
+        None
+    };
+
+    let mut board = Board::new(board_data);
+    board.allow_wrong = args.allow_wrong;
+
+    let mut restored = false;
+    if let Some(save_file) = &save_file {
+        match save_file.restore(&mut board) {
+            Ok(found) => restored = found,
+            Err(e) => {
+                eprintln!(
+                    "error: could not restore progress from '{}': {e}",
+                    save_file.path.display()
+                );
+                eprintln!("hint: delete it or use --no-save");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    // restored progress comes with its own position, as long as it still
+    // shows some of the board
+    let layout = terminal::size().ok().map(Layout::new);
+    if let Some(layout) = &layout {
+        if restored {
+            board.keep_in_view(layout);
+        } else {
+            board.center(layout);
+        }
+    }
+
+    // options given on the command line win over restored progress
+    if args.half_blocks && board.render_mode != RenderMode::HalfBlocks {
+        let (col, row) = layout.as_ref().map_or((0, 0), Layout::board_center);
+        board.set_render_mode(RenderMode::HalfBlocks, col, row);
+    }
+    if let Some(color) = args.color {
+        board.palette.selected_color = color;
+    }
+
+    guard::install_panic_hook();
+    run(&mut board, &args, save_file.as_ref())
+}
@@ -0,0 +1,490 @@
+// reader for the whole Netpbm family:
+// P1/P4 bitmaps, P2/P5 greymaps, P3/P6 pixmaps and P7 (PAM)
+
+use crate::{
+    board::{BoardLoadError, ImageData, Position},
+    palette::RGB,
+};
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn position(&self) -> Position {
+        let pos = self.pos.min(self.data.len());
+
+        Position {
+            line: self.data[..pos].iter().filter(|&&b| b == b'\n').count() + 1,
+            offset: pos,
+        }
+    }
+
+    fn bad_header(&self, message: String) -> BoardLoadError {
+        BoardLoadError::BadHeader {
+            position: self.position(),
+            message,
+        }
+    }
+
+    fn bad_pixel(&self, message: String) -> BoardLoadError {
+        BoardLoadError::BadPixelData {
+            position: self.position(),
+            message,
+        }
+    }
+
+    fn truncated(&self) -> BoardLoadError {
+        BoardLoadError::Truncated {
+            position: self.position(),
+        }
+    }
+
+    // comments start with '#' anywhere and run to the end of the line
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if b == b'#' {
+                while let Some(&b) = self.data.get(self.pos) {
+                    if b == b'\n' || b == b'\r' {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+            } else if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Option<&'a [u8]> {
+        self.skip_whitespace();
+
+        let start = self.pos;
+        while let Some(&b) = self.data.get(self.pos) {
+            if b.is_ascii_whitespace() || b == b'#' {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        (self.pos > start).then(|| &self.data[start..self.pos])
+    }
+
+    fn header_number(&mut self, name: &str) -> Result<u32, BoardLoadError> {
+        let Some(token) = self.token() else {
+            return Err(self.bad_header(format!("missing {name}")));
+        };
+
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|t| t.parse::<u32>().ok())
+            .ok_or_else(|| {
+                self.bad_header(format!(
+                    "invalid {name} '{}'",
+                    String::from_utf8_lossy(token)
+                ))
+            })
+    }
+
+    // binary rasters start after exactly one whitespace character
+    fn begin_raster(&mut self) -> Result<(), BoardLoadError> {
+        match self.data.get(self.pos) {
+            Some(b) if b.is_ascii_whitespace() => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(self.bad_header("expected whitespace before pixel data".to_string())),
+            None => Err(self.truncated()),
+        }
+    }
+
+    fn ascii_sample(&mut self, maxval: u32) -> Result<u32, BoardLoadError> {
+        let Some(token) = self.token() else {
+            return Err(self.truncated());
+        };
+
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|t| t.parse::<u32>().ok())
+            .filter(|&v| v <= maxval)
+            .ok_or_else(|| {
+                self.bad_pixel(format!(
+                    "expected a sample between 0 and {maxval} but got '{}'",
+                    String::from_utf8_lossy(token)
+                ))
+            })
+    }
+
+    fn binary_sample(&mut self, maxval: u32) -> Result<u32, BoardLoadError> {
+        let size = if maxval < 256 { 1 } else { 2 };
+        let Some(bytes) = self.data.get(self.pos..self.pos + size) else {
+            return Err(self.truncated());
+        };
+
+        let value = bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u32);
+        if value > maxval {
+            return Err(self.bad_pixel(format!("sample {value} is greater than maxval {maxval}")));
+        }
+        self.pos += size;
+
+        Ok(value)
+    }
+
+    // P1 bits may or may not be separated by whitespace
+    fn ascii_bit(&mut self) -> Result<bool, BoardLoadError> {
+        self.skip_whitespace();
+
+        match self.data.get(self.pos) {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            Some(&b) => Err(self.bad_pixel(format!("expected 0 or 1 but got '{}'", b as char))),
+            None => Err(self.truncated()),
+        }
+    }
+}
+
+fn scale(value: u32, maxval: u32) -> u8 {
+    ((value * 255 + maxval / 2) / maxval) as u8
+}
+
+fn check_size(reader: &Reader, width: u32, height: u32) -> Result<(usize, usize), BoardLoadError> {
+    if width == 0 || height == 0 {
+        return Err(reader.bad_header(format!("invalid image size {width}x{height}")));
+    }
+    if (width as usize).checked_mul(height as usize).is_none() {
+        return Err(reader.bad_header(format!("image size {width}x{height} is too large")));
+    }
+
+    Ok((width as usize, height as usize))
+}
+
+fn check_maxval(reader: &Reader, maxval: u32) -> Result<u32, BoardLoadError> {
+    if maxval == 0 || maxval > 65535 {
+        return Err(reader.bad_header(format!("maxval must be between 1 and 65535, got {maxval}")));
+    }
+
+    Ok(maxval)
+}
+
+fn read_bitmap(
+    reader: &mut Reader,
+    width: usize,
+    height: usize,
+    binary: bool,
+) -> Result<Vec<Option<RGB>>, BoardLoadError> {
+    const BLACK: RGB = RGB { r: 0, g: 0, b: 0 };
+    const WHITE: RGB = RGB {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    let mut pixels = Vec::new();
+    if binary {
+        // each row is padded to a whole byte
+        let row_size = width.div_ceil(8);
+        for _ in 0..height {
+            let Some(row) = reader.data.get(reader.pos..reader.pos + row_size) else {
+                return Err(reader.truncated());
+            };
+            reader.pos += row_size;
+
+            for x in 0..width {
+                let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+                pixels.push(Some(if bit == 1 { BLACK } else { WHITE }));
+            }
+        }
+    } else {
+        for _ in 0..width * height {
+            pixels.push(Some(if reader.ascii_bit()? { BLACK } else { WHITE }));
+        }
+    }
+
+    Ok(pixels)
+}
+
+fn read_samples(
+    reader: &mut Reader,
+    width: usize,
+    height: usize,
+    depth: usize,
+    maxval: u32,
+    binary: bool,
+) -> Result<Vec<Option<RGB>>, BoardLoadError> {
+    let mut pixels = Vec::new();
+    let mut samples = [0; 4];
+
+    for _ in 0..width * height {
+        for sample in samples.iter_mut().take(depth) {
+            *sample = if binary {
+                reader.binary_sample(maxval)?
+            } else {
+                reader.ascii_sample(maxval)?
+            };
+        }
+
+        // greyscale tuples only have one color channel
+        let (r, g, b) = if depth >= 3 {
+            (samples[0], samples[1], samples[2])
+        } else {
+            (samples[0], samples[0], samples[0])
+        };
+
+        // mostly transparent pixels are not part of the puzzle
+        if matches!(depth, 2 | 4) && samples[depth - 1] * 2 < maxval {
+            pixels.push(None);
+            continue;
+        }
+
+        pixels.push(Some(RGB::new(
+            scale(r, maxval),
+            scale(g, maxval),
+            scale(b, maxval),
+        )));
+    }
+
+    Ok(pixels)
+}
+
+fn decode_pam(reader: &mut Reader) -> Result<ImageData, BoardLoadError> {
+    let mut width = None;
+    let mut height = None;
+    let mut depth = None;
+    let mut maxval = None;
+
+    loop {
+        let Some(key) = reader.token() else {
+            return Err(reader.bad_header("missing ENDHDR".to_string()));
+        };
+
+        match key {
+            b"WIDTH" => width = Some(reader.header_number("width")?),
+            b"HEIGHT" => height = Some(reader.header_number("height")?),
+            b"DEPTH" => depth = Some(reader.header_number("depth")?),
+            b"MAXVAL" => maxval = Some(reader.header_number("maxval")?),
+            // the tuple type is implied by the depth
+            b"TUPLTYPE" => {
+                reader.token();
+            }
+            b"ENDHDR" => break,
+            _ => {
+                return Err(reader.bad_header(format!(
+                    "unknown header field '{}'",
+                    String::from_utf8_lossy(key)
+                )))
+            }
+        }
+    }
+
+    let (Some(width), Some(height), Some(depth), Some(maxval)) = (width, height, depth, maxval)
+    else {
+        return Err(reader.bad_header("WIDTH, HEIGHT, DEPTH and MAXVAL are required".to_string()));
+    };
+
+    let (width, height) = check_size(reader, width, height)?;
+    let maxval = check_maxval(reader, maxval)?;
+    if !(1..=4).contains(&depth) {
+        return Err(reader.bad_header(format!("unsupported depth {depth}, expected 1 to 4")));
+    }
+
+    reader.begin_raster()?;
+    let pixels = read_samples(reader, width, height, depth as usize, maxval, true)?;
+
+    Ok(ImageData {
+        width,
+        height,
+        pixels,
+    })
+}
+
+pub fn decode(data: &[u8]) -> Result<ImageData, BoardLoadError> {
+    let mut reader = Reader { data, pos: 0 };
+
+    let magic = match data.get(0..2) {
+        Some([b'P', n @ b'1'..=b'7']) => n - b'0',
+        _ => {
+            return Err(BoardLoadError::BadMagic {
+                position: reader.position(),
+            })
+        }
+    };
+    reader.pos = 2;
+
+    if magic == 7 {
+        return decode_pam(&mut reader);
+    }
+
+    let binary = magic >= 4;
+    let bitmap = magic == 1 || magic == 4;
+    let depth = if magic == 3 || magic == 6 { 3 } else { 1 };
+
+    let width = reader.header_number("width")?;
+    let height = reader.header_number("height")?;
+    let (width, height) = check_size(&reader, width, height)?;
+    let maxval = if bitmap {
+        1
+    } else {
+        let maxval = reader.header_number("maxval")?;
+        check_maxval(&reader, maxval)?
+    };
+
+    if binary {
+        reader.begin_raster()?;
+    }
+
+    let pixels = if bitmap {
+        read_bitmap(&mut reader, width, height, binary)?
+    } else {
+        read_samples(&mut reader, width, height, depth, maxval, binary)?
+    };
+
+    Ok(ImageData {
+        width,
+        height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_err(data: &[u8]) -> BoardLoadError {
+        match decode(data) {
+            Ok(_) => panic!("decoded {:?}", String::from_utf8_lossy(data)),
+            Err(e) => e,
+        }
+    }
+
+    fn assert_position(position: Position, line: usize, offset: usize) {
+        assert_eq!((position.line, position.offset), (line, offset));
+    }
+
+    fn assert_bad_header(data: &[u8], expected: &str, line: usize, offset: usize) {
+        match decode_err(data) {
+            BoardLoadError::BadHeader { position, message } => {
+                assert_eq!(message, expected);
+                assert_position(position, line, offset);
+            }
+            e => panic!("expected a bad header, got {e:?}"),
+        }
+    }
+
+    fn assert_bad_pixel(data: &[u8], line: usize, offset: usize) {
+        match decode_err(data) {
+            BoardLoadError::BadPixelData { position, .. } => {
+                assert_position(position, line, offset)
+            }
+            e => panic!("expected bad pixel data, got {e:?}"),
+        }
+    }
+
+    fn assert_truncated(data: &[u8], line: usize, offset: usize) {
+        match decode_err(data) {
+            BoardLoadError::Truncated { position } => assert_position(position, line, offset),
+            e => panic!("expected truncated pixel data, got {e:?}"),
+        }
+    }
+
+    #[test]
+    fn decodes_pixmap() {
+        let image = decode(b"P3\n# comment\n2 1\n255\n255 0 0  0 0 255\n").unwrap();
+
+        assert_eq!((image.width, image.height), (2, 1));
+        assert!(image.pixels == [Some(RGB::new(255, 0, 0)), Some(RGB::new(0, 0, 255))]);
+    }
+
+    #[test]
+    fn bad_magic() {
+        for data in [&b"P8\n1 1\n"[..], b"GIF89a", b"P", b""] {
+            match decode_err(data) {
+                BoardLoadError::BadMagic { position } => assert_position(position, 1, 0),
+                e => panic!("expected a bad magic number, got {e:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn missing_header_field() {
+        assert_bad_header(b"P3\n", "missing width", 2, 3);
+        assert_bad_header(b"P3\n2\n", "missing height", 3, 5);
+        assert_bad_header(b"P3 2 1", "missing maxval", 1, 6);
+        assert_bad_header(
+            b"P7\nWIDTH 1\nHEIGHT 1\n",
+            "missing ENDHDR",
+            4,
+            b"P7\nWIDTH 1\nHEIGHT 1\n".len(),
+        );
+        assert_bad_header(
+            b"P7\nWIDTH 1\nHEIGHT 1\nENDHDR\n",
+            "WIDTH, HEIGHT, DEPTH and MAXVAL are required",
+            4,
+            26,
+        );
+    }
+
+    #[test]
+    fn invalid_header_field() {
+        assert_bad_header(b"P3\n2 x\n255\n", "invalid height 'x'", 2, 6);
+        assert_bad_header(b"P3\n-2 1\n255\n", "invalid width '-2'", 2, 5);
+        assert_bad_header(
+            b"P7\nWIDTH 1\nSIZE 1\n",
+            "unknown header field 'SIZE'",
+            3,
+            15,
+        );
+    }
+
+    #[test]
+    fn maxval_out_of_range() {
+        assert_bad_header(
+            b"P2\n1 1\n0\n0\n",
+            "maxval must be between 1 and 65535, got 0",
+            3,
+            8,
+        );
+        assert_bad_header(
+            b"P2\n1 1\n65536\n0\n",
+            "maxval must be between 1 and 65535, got 65536",
+            3,
+            12,
+        );
+    }
+
+    #[test]
+    fn sample_over_maxval() {
+        assert_bad_pixel(b"P2\n1 1\n15\n16\n", 4, 12);
+        assert_bad_pixel(b"P5\n1 1\n15\n\x10", 4, 10);
+        assert_bad_pixel(b"P5\n1 1\n256\n\x01\x01", 4, 11);
+        assert_bad_pixel(b"P1\n1 1\n2\n", 3, 7);
+    }
+
+    #[test]
+    fn truncated_ascii_raster() {
+        assert_truncated(b"P3\n2 1\n255\n1 2 3 4 5\n", 5, 21);
+        assert_truncated(b"P1\n3 1\n01", 3, 9);
+    }
+
+    #[test]
+    fn truncated_binary_raster() {
+        assert_truncated(b"P6\n2 1\n255\n\x01\x02\x03\x04", 4, 15);
+        assert_truncated(b"P5\n1 1\n65535\n\x01", 4, 13);
+        assert_truncated(b"P4\n9 2\n\xff\xff\xff", 3, 9);
+        assert_truncated(b"P5\n1 1\n255", 3, 10);
+    }
+
+    #[test]
+    fn zero_size() {
+        assert_bad_header(b"P1\n0 1\n", "invalid image size 0x1", 2, 6);
+        assert_bad_header(b"P6\n4 0\n255\n", "invalid image size 4x0", 2, 6);
+    }
+}
@@ -1,16 +1,13 @@
-use std::io;
-
 use crossterm::{
-    cursor,
-    event::MouseButton,
-    queue,
-    style::{self, Attribute, Color},
-    terminal,
+    event::{KeyCode, MouseButton},
+    style::Color,
 };
 
 use crate::{
     board::BoardDataColor,
     input::{Input, Rect},
+    layout::Layout,
+    screen::{Screen, Style},
 };
 
 #[derive(Clone, Copy)]
@@ -22,13 +19,41 @@ pub struct PaletteColor {
     count: u32,
 }
 
+// index into the palette colors
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ColorId(pub u16);
+
+impl ColorId {
+    // number of distinct ids
+    pub const COUNT: usize = u16::MAX as usize + 1;
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
 pub struct Palette {
     pub colors: Vec<PaletteColor>,
 
-    pub selected_color: u8,
+    pub selected_color: ColorId,
+    // selection the page was last synced with
+    last_selected_color: ColorId,
+    pub page: usize,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+const CELL_WIDTH: u16 = 6;
+const CELL_HEIGHT: u16 = 3;
+const CELLS_PER_ROW: u16 = 5;
+const CELLS_PER_COL: u16 = 2;
+const TOTAL_WIDTH: u16 = CELL_WIDTH * CELLS_PER_ROW;
+const PAGE_SIZE: usize = (CELLS_PER_ROW * CELLS_PER_COL) as usize;
+
+// color cells with the side bars, arrows and the line above them
+pub const PALETTE_WIDTH: u16 = TOTAL_WIDTH + 12;
+pub const PALETTE_HEIGHT: u16 = CELLS_PER_COL * CELL_HEIGHT + 1;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct RGB {
     pub r: u8,
     pub g: u8,
@@ -40,7 +65,27 @@ impl RGB {
         Self { r, g, b }
     }
 
-    pub fn to_color(&self) -> Color {
+    pub fn scale(self, factor: f32) -> Self {
+        let scale = |c: u8| (c as f32 * factor).round().clamp(0.0, 255.0) as u8;
+        Self::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+
+    // moves `amount` of the way towards `other`
+    pub fn mix(self, other: RGB, amount: f32) -> Self {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+
+    // perceived brightness from 0 to 1
+    pub fn brightness(self) -> f32 {
+        (0.299 * self.r as f32 + 0.587 * self.g as f32 + 0.114 * self.b as f32) / 255.0
+    }
+
+    pub fn to_color(self) -> Color {
         Color::Rgb {
             r: self.r,
             g: self.g,
@@ -59,6 +104,22 @@ impl From<PaletteColor> for Color {
     }
 }
 
+fn to_radix(mut n: usize, radix: usize) -> String {
+    const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+    let mut digits = Vec::new();
+    loop {
+        digits.push(DIGITS[n % radix]);
+        n /= radix;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+
+    String::from_utf8(digits).unwrap_or_default()
+}
+
 impl Palette {
     pub fn new(colors: Vec<BoardDataColor>) -> Self {
         Self {
@@ -72,51 +133,154 @@ impl Palette {
                     count: c.count,
                 })
                 .collect::<Vec<_>>(),
-            selected_color: 0,
+            selected_color: ColorId(0),
+            last_selected_color: ColorId(0),
+            page: 0,
         }
     }
 
-    pub fn get_color(&self, index: u8) -> Color {
-        self.colors[index as usize].into()
+    pub fn get_color(&self, color: ColorId) -> Color {
+        self.colors[color.index()].into()
     }
 
-    pub fn update(&mut self, input: &Input) -> io::Result<()> {
-        const CELL_WIDTH: u16 = 6;
-        const CELL_HEIGHT: u16 = 3;
-        const CELLS_PER_ROW: u16 = 5;
-        const CELLS_PER_COL: u16 = 2;
-        const TOTAL_WIDTH: u16 = CELL_WIDTH * CELLS_PER_ROW as u16;
+    pub fn get_rgb(&self, color: ColorId) -> RGB {
+        let color = self.colors[color.index()];
+        RGB::new(color.r, color.g, color.b)
+    }
 
-        let size = terminal::size()?;
-        let x = (size.0 - TOTAL_WIDTH) / 2;
-        let y = size.1 - 2 * CELL_HEIGHT - 1;
+    // labels are the 1-based color number, in decimal when every color of
+    // the palette fits in `width` characters, in base 36 otherwise
+    fn label_radix(&self, width: usize) -> Option<usize> {
+        [10, 36]
+            .into_iter()
+            .find(|&radix| to_radix(self.colors.len(), radix).len() <= width)
+    }
+
+    // number of characters used by the widest label
+    fn label_digits(&self, width: usize) -> Option<usize> {
+        let radix = self.label_radix(width)?;
+        Some(to_radix(self.colors.len(), radix).len())
+    }
+
+    pub fn label(&self, color: ColorId, width: usize) -> Option<String> {
+        let radix = self.label_radix(width)?;
+        Some(to_radix(color.index() + 1, radix))
+    }
+
+    fn page_count(&self) -> usize {
+        self.colors.len().div_ceil(PAGE_SIZE)
+    }
+
+    fn set_page(&mut self, page: isize) {
+        self.page = page.clamp(0, self.page_count() as isize - 1) as usize;
+    }
+
+    // top left corner of the color cells
+    fn origin(layout: &Layout) -> (i32, i32) {
+        (layout.palette.x as i32 + 6, layout.palette.y as i32)
+    }
+
+    fn arrow_rects(layout: &Layout) -> (Rect, Rect) {
+        let (x, y) = Self::origin(layout);
+
+        (
+            Rect {
+                x: (x - 6) as i16,
+                y: (y + 3) as i16,
+                width: 4,
+                height: 3,
+            },
+            Rect {
+                x: (x + TOTAL_WIDTH as i32 + 3) as i16,
+                y: (y + 3) as i16,
+                width: 3,
+                height: 3,
+            },
+        )
+    }
+
+    pub fn update(&mut self, input: &Input, layout: &Layout) {
+        let (x, y) = Self::origin(layout);
+
+        // number keys pick a color on the current page, tab cycles through
+        // all of them
+        for (i, key) in "1234567890".chars().enumerate() {
+            let color_index = self.page * PAGE_SIZE + i;
+            if input.is_key_pressed(KeyCode::Char(key)) && color_index < self.colors.len() {
+                self.selected_color = ColorId(color_index as u16);
+            }
+        }
+        let count = self.colors.len();
+        if input.is_key_pressed(KeyCode::Tab) {
+            self.selected_color = ColorId(((self.selected_color.index() + 1) % count) as u16);
+        }
+        if input.is_key_pressed(KeyCode::BackTab) {
+            self.selected_color =
+                ColorId(((self.selected_color.index() + count - 1) % count) as u16);
+        }
+
+        // follow the selection wherever it was changed from
+        if self.selected_color != self.last_selected_color {
+            self.page = self.selected_color.index() / PAGE_SIZE;
+            self.last_selected_color = self.selected_color;
+        }
+
+        let (left_arrow, right_arrow) = Self::arrow_rects(layout);
+        if input.is_mouse_clicked_in(MouseButton::Left, &left_arrow)
+            || input.is_key_pressed(KeyCode::Char('['))
+        {
+            self.set_page(self.page as isize - 1);
+        }
+        if input.is_mouse_clicked_in(MouseButton::Left, &right_arrow)
+            || input.is_key_pressed(KeyCode::Char(']'))
+        {
+            self.set_page(self.page as isize + 1);
+        }
+
+        for frame_mouse in &input.frame_mouses {
+            if frame_mouse.scroll != 0
+                && layout
+                    .palette
+                    .contains(frame_mouse.x as i16, frame_mouse.y as i16)
+            {
+                self.set_page(self.page as isize - frame_mouse.scroll as isize);
+            }
+        }
 
         for row in 0..CELLS_PER_COL {
             for col in 0..CELLS_PER_ROW {
+                let color_index = self.page * PAGE_SIZE + (row * CELLS_PER_ROW + col) as usize;
+                if color_index >= self.colors.len() {
+                    break;
+                }
+
                 if input.is_mouse_button_down_in(
                     MouseButton::Left,
                     Rect {
-                        x: (x + col * CELL_WIDTH) as i16,
-                        y: (y + row * CELL_HEIGHT + 1) as i16,
+                        x: (x + (col * CELL_WIDTH) as i32) as i16,
+                        y: (y + (row * CELL_HEIGHT) as i32 + 1) as i16,
                         width: CELL_WIDTH,
                         height: CELL_HEIGHT,
                     },
                 ) {
-                    self.selected_color = (row * CELLS_PER_ROW + col) as u8;
+                    self.selected_color = ColorId(color_index as u16);
+                    self.last_selected_color = self.selected_color;
                 }
             }
         }
-
-        Ok(())
     }
 
-    fn render_cell(&self, x: u16, y: u16, color_index: u8) -> io::Result<()> {
+    fn render_cell(
+        &self,
+        screen: &mut Screen,
+        x: i32,
+        y: i32,
+        color_index: ColorId,
+        label_width: usize,
+    ) {
         const BORDER_COLOR: Color = Color::Black;
-        let color = self.colors[color_index as usize];
-        let brightness =
-            (0.299 * color.r as f32 + 0.587 * color.g as f32 + 0.114 * color.b as f32) / 255.0;
-
-        let mut stdout = io::stdout();
+        let color = self.colors[color_index.index()];
+        let brightness = self.get_rgb(color_index).brightness();
 
         let background_color = color.into();
         let (zero_foreground_color, foreground_color) = if brightness > 0.5 {
@@ -131,60 +295,52 @@ impl Palette {
             )
         };
 
-        queue!(
-            stdout,
-            cursor::MoveTo(x, y),
-            style::SetBackgroundColor(background_color),
-            style::SetForegroundColor(BORDER_COLOR),
-            style::Print("🭽▔▔▔▔🭾"),
-            cursor::MoveTo(x, y + 1),
-            style::Print("▏ "),
-        )?;
-
-        if color_index == self.selected_color {
-            queue!(stdout, style::SetAttribute(Attribute::Bold))?;
-        }
-        if color.painted == color.count {
-            queue!(
-                stdout,
-                style::SetForegroundColor(zero_foreground_color),
-                style::Print("OK")
-            )?;
-        } else {
-            if color_index < 9 {
-                queue!(
-                    stdout,
-                    style::SetForegroundColor(zero_foreground_color),
-                    style::Print("0"),
-                    style::SetForegroundColor(foreground_color),
-                    style::Print(format!("{}", color_index + 1)),
-                )?;
+        let border = Style::new(BORDER_COLOR, background_color);
+        screen.print(x, y, "🭽▔▔▔▔🭾", border);
+        screen.print(x, y + 1, "▏", border);
+
+        let label_style = |fg| {
+            let style = Style::new(fg, background_color);
+            if color_index == self.selected_color {
+                style.bold()
             } else {
-                queue!(
-                    stdout,
-                    style::SetForegroundColor(foreground_color),
-                    style::Print(format!("{}", color_index + 1)),
-                )?;
+                style
             }
+        };
+        if color.painted == color.count {
+            screen.print(x + 1, y + 1, " OK ", label_style(zero_foreground_color));
+        } else {
+            // fall back to the widest labels when the board has no room
+            // for any, so every color can still be told apart
+            let (label, digits) = self
+                .label(color_index, label_width)
+                .zip(self.label_digits(label_width))
+                .or_else(|| self.label(color_index, 4).zip(self.label_digits(4)))
+                .unwrap_or_default();
+
+            // pad with dimmed zeros, to at least two digits when there is room
+            let zeros = digits.max(label_width.min(2)).saturating_sub(label.len());
+            let padding = 4usize.saturating_sub(zeros + label.len());
+            let text = format!(
+                "{}{}{label}{}",
+                " ".repeat(padding / 2),
+                "0".repeat(zeros),
+                " ".repeat(padding - padding / 2)
+            );
+            screen.print(x + 1, y + 1, &text, label_style(foreground_color));
+            screen.print(
+                x + 1 + (padding / 2) as i32,
+                y + 1,
+                &"0".repeat(zeros),
+                label_style(zero_foreground_color),
+            );
         }
 
-        queue!(
-            stdout,
-            style::SetAttribute(Attribute::NormalIntensity),
-            style::SetForegroundColor(BORDER_COLOR),
-            style::Print(" ▕"),
-            cursor::MoveTo(x, y + 2),
-            style::Print("🭼▁▁▁▁🭿"),
-            style::ResetColor,
-            //
-            style::SetForegroundColor(BORDER_COLOR),
-            cursor::MoveTo(x + 6, y),
-            style::Print("▏"),
-            cursor::MoveTo(x + 6, y + 1),
-            style::Print("▏"),
-            cursor::MoveTo(x + 6, y + 2),
-            style::Print("▏"),
-        )?;
+        screen.print(x + 5, y + 1, "▕", border);
+        screen.print(x, y + 2, "🭼▁▁▁▁🭿", border);
+        for oy in 0..3 {
+            screen.print(x + 6, y + oy, "▏", Style::fg(BORDER_COLOR));
+        }
 
         if color.painted != color.count && self.selected_color == color_index {
             let fill = (6.0 * color.painted as f32 / color.count as f32).round() as usize;
@@ -201,103 +357,74 @@ impl Palette {
                 4
             };
 
-            queue!(
-                stdout,
-                cursor::MoveTo(x + 1, y + 2),
-                style::SetBackgroundColor(background_color),
-                style::Print("\u{ee00}\u{ee01}\u{ee01}\u{ee02}"),
-                cursor::MoveTo(x + 1, y + 2),
-                style::Print(&"\u{ee03}\u{ee04}\u{ee04}\u{ee05}"[0..fill * 3]),
-            )?;
+            screen.print(x + 1, y + 2, "\u{ee00}\u{ee01}\u{ee01}\u{ee02}", border);
+            screen.print(
+                x + 1,
+                y + 2,
+                &"\u{ee03}\u{ee04}\u{ee04}\u{ee05}"[0..fill * 3],
+                border,
+            );
         }
-
-        queue!(stdout, style::ResetColor)?;
-
-        Ok(())
     }
 
-    pub fn render(&self) -> io::Result<()> {
-        let mut stdout = io::stdout();
-        let size = terminal::size()?;
-
-        const CELL_WIDTH: u16 = 6;
-        const CELL_HEIGHT: u16 = 3;
-        const CELLS_PER_ROW: u16 = 5;
-        const CELLS_PER_COL: u16 = 2;
-        const TOTAL_WIDTH: u16 = CELL_WIDTH * CELLS_PER_ROW as u16;
-
-        let x = (size.0 - TOTAL_WIDTH) / 2;
-        let y = size.1 - 2 * CELL_HEIGHT - 1;
-
-        for oy in 1..(CELLS_PER_ROW * CELL_HEIGHT) {
-            queue!(
-                stdout,
-                cursor::MoveTo(x - 1, y + oy),
-                style::SetForegroundColor(Color::Black),
-                style::Print("█"),
-            )?;
+    // labels must match the ones on the board, which depend on how much
+    // room the board has for them
+    pub fn render(&self, screen: &mut Screen, layout: &Layout, label_width: usize) {
+        let (x, y) = Self::origin(layout);
+        let total_width = TOTAL_WIDTH as i32;
+
+        for oy in 1..(CELLS_PER_COL * CELL_HEIGHT + 1) as i32 {
+            screen.print(x - 1, y + oy, "█", Style::fg(Color::Black));
         }
 
         for row in 0..CELLS_PER_COL {
             for col in 0..CELLS_PER_ROW {
-                /*
-                draw_cell(
-                    x + col * CELL_WIDTH,
-                    y + row * CELL_HEIGHT + 1,
-                    (row * CELLS_PER_ROW + col) as u8,
-                )?;
-                */
-                let color_index = (row * CELLS_PER_ROW + col) as u8;
-                if color_index as usize >= self.colors.len() {
+                let color_index = self.page * PAGE_SIZE + (row * CELLS_PER_ROW + col) as usize;
+                if color_index >= self.colors.len() {
                     break;
                 }
 
-                self.render_cell(x + col * CELL_WIDTH, y + row * CELL_HEIGHT + 1, color_index)?;
+                self.render_cell(
+                    screen,
+                    x + (col * CELL_WIDTH) as i32,
+                    y + (row * CELL_HEIGHT) as i32 + 1,
+                    ColorId(color_index as u16),
+                    label_width,
+                );
             }
         }
 
-        for oy in 1..(CELLS_PER_ROW * CELL_HEIGHT) {
-            queue!(
-                stdout,
-                cursor::MoveTo(x + TOTAL_WIDTH, y + oy),
-                style::SetForegroundColor(Color::Black),
-                style::Print("█"),
-            )?;
+        for oy in 1..(CELLS_PER_COL * CELL_HEIGHT + 1) as i32 {
+            screen.print(x + total_width, y + oy, "█", Style::fg(Color::Black));
         }
 
-        queue!(
-            stdout,
-            cursor::MoveTo(x - 1, size.1 - 7),
-            style::SetForegroundColor(Color::Black),
-            style::Print("🬭".repeat(TOTAL_WIDTH as usize + 2)),
-        )?;
-
-        // arrows
-        let ax = x + TOTAL_WIDTH + 3;
-        let ay = size.1 - 5;
-        queue!(
-            stdout,
-            cursor::MoveTo(ax, ay + 1),
-            style::Print("▀▄"),
-            cursor::MoveTo(ax, ay + 2),
-            style::Print(" ▄▀"),
-            cursor::MoveTo(ax, ay + 3),
-            style::Print("▀"),
-        )?;
+        screen.print(
+            x - 1,
+            y,
+            &"🬭".repeat(TOTAL_WIDTH as usize + 2),
+            Style::fg(Color::Black),
+        );
+
+        // arrows, dimmed when there is no page in their direction
+        let arrow_style = |enabled: bool| {
+            Style::fg(if enabled {
+                Color::Black
+            } else {
+                Color::DarkGrey
+            })
+        };
+
+        let ax = x + total_width + 3;
+        let ay = y + 2;
+        let style = arrow_style(self.page + 1 < self.page_count());
+        screen.print(ax, ay + 1, "▀▄", style);
+        screen.print(ax, ay + 2, " ▄▀", style);
+        screen.print(ax, ay + 3, "▀", style);
 
         let ax = x - 6;
-        let ay = size.1 - 5;
-        queue!(
-            stdout,
-            cursor::MoveTo(ax, ay + 1),
-            style::Print(" ▄▀"),
-            cursor::MoveTo(ax, ay + 2),
-            style::Print("▀▄  "),
-            cursor::MoveTo(ax, ay + 3),
-            style::Print("  ▀"),
-            style::ResetColor,
-        )?;
-
-        Ok(())
+        let style = arrow_style(self.page > 0);
+        screen.print(ax, ay + 1, " ▄▀", style);
+        screen.print(ax, ay + 2, "▀▄  ", style);
+        screen.print(ax, ay + 3, "  ▀", style);
     }
 }
@@ -0,0 +1,370 @@
+// palette reduction: median cut to seed the palette, then a few rounds
+// of k-means in CIE Lab space so the result follows perceived color
+
+use std::collections::HashMap;
+
+use crate::{board::ImageData, palette::RGB};
+
+const KMEANS_ITERATIONS: usize = 8;
+
+// histograms with more colors than this are built from 5 bits per channel
+// instead, which bounds the work of building the palette for photos
+const MAX_HISTOGRAM_COLORS: usize = 1 << 15;
+const BUCKET_SHIFT: u32 = 3;
+
+#[derive(Clone, Copy)]
+struct Lab {
+    l: f32,
+    a: f32,
+    b: f32,
+}
+
+impl Lab {
+    fn distance(self, other: Lab) -> f32 {
+        (self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2)
+    }
+}
+
+// D65 reference white
+const WHITE_X: f32 = 0.950_47;
+const WHITE_Z: f32 = 1.088_83;
+
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn rgb_to_lab(r: f32, g: f32, b: f32) -> Lab {
+    let r = to_linear(r / 255.0);
+    let g = to_linear(g / 255.0);
+    let b = to_linear(b / 255.0);
+
+    let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / WHITE_X;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+    let z = (0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b) / WHITE_Z;
+
+    let f = |t: f32| {
+        if t > 0.008_856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+
+    Lab {
+        l: 116.0 * fy - 16.0,
+        a: 500.0 * (fx - fy),
+        b: 200.0 * (fy - fz),
+    }
+}
+
+fn lab_to_rgb(lab: Lab) -> RGB {
+    let fy = (lab.l + 16.0) / 116.0;
+    let fx = fy + lab.a / 500.0;
+    let fz = fy - lab.b / 200.0;
+
+    let f = |t: f32| {
+        if t > 0.206_893 {
+            t * t * t
+        } else {
+            (t - 16.0 / 116.0) / 7.787
+        }
+    };
+    let (x, y, z) = (f(fx) * WHITE_X, f(fy), f(fz) * WHITE_Z);
+
+    let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+    let g = -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z;
+    let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+
+    let to_u8 = |c: f32| (from_linear(c.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    RGB::new(to_u8(r), to_u8(g), to_u8(b))
+}
+
+fn channel(rgb: RGB, index: usize) -> u8 {
+    match index {
+        0 => rgb.r,
+        1 => rgb.g,
+        _ => rgb.b,
+    }
+}
+
+// colors within a bucket are merged into their weighted average
+fn bucket_histogram(counts: &HashMap<RGB, u32>) -> Vec<(RGB, u32)> {
+    let mut buckets: HashMap<(u8, u8, u8), [u64; 4]> = HashMap::new();
+    for (&rgb, &count) in counts {
+        let key = (
+            rgb.r >> BUCKET_SHIFT,
+            rgb.g >> BUCKET_SHIFT,
+            rgb.b >> BUCKET_SHIFT,
+        );
+        let sums = buckets.entry(key).or_default();
+        let count = count as u64;
+        sums[0] += rgb.r as u64 * count;
+        sums[1] += rgb.g as u64 * count;
+        sums[2] += rgb.b as u64 * count;
+        sums[3] += count;
+    }
+
+    buckets
+        .into_values()
+        .map(|[r, g, b, count]| {
+            let average = |sum: u64| ((sum + count / 2) / count) as u8;
+            (RGB::new(average(r), average(g), average(b)), count as u32)
+        })
+        .collect()
+}
+
+// colors of a median cut box along with their range in each channel,
+// worked out once when the box is made
+struct ColorBox {
+    colors: Vec<(RGB, u32)>,
+    ranges: [u8; 3],
+}
+
+impl ColorBox {
+    fn new(colors: Vec<(RGB, u32)>) -> Self {
+        let mut ranges = [0; 3];
+        for (c, range) in ranges.iter_mut().enumerate() {
+            let channels = colors.iter().map(|&(rgb, _)| channel(rgb, c));
+            let min = channels.clone().min().unwrap_or(0);
+            let max = channels.max().unwrap_or(0);
+            *range = max - min;
+        }
+
+        Self { colors, ranges }
+    }
+}
+
+fn median_cut(histogram: &[(RGB, u32)], colors: usize) -> Vec<RGB> {
+    let mut boxes = vec![ColorBox::new(histogram.to_vec())];
+
+    while boxes.len() < colors {
+        // split the box with the widest channel range
+        let mut best: Option<(usize, usize, u8)> = None;
+        for (i, color_box) in boxes.iter().enumerate() {
+            if color_box.colors.len() < 2 {
+                continue;
+            }
+
+            for (c, &range) in color_box.ranges.iter().enumerate() {
+                if best.is_none_or(|(_, _, best_range)| range > best_range) {
+                    best = Some((i, c, range));
+                }
+            }
+        }
+
+        let Some((i, c, _)) = best else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(i).colors;
+        colors.sort_by_key(|&(rgb, _)| channel(rgb, c));
+
+        // split at the weighted median, keeping both halves non empty
+        let total = colors.iter().map(|&(_, count)| count as u64).sum::<u64>();
+        let mut seen = 0;
+        let mut split = 1;
+        for (j, &(_, count)) in colors.iter().enumerate().take(colors.len() - 1) {
+            seen += count as u64;
+            split = j + 1;
+            if seen * 2 >= total {
+                break;
+            }
+        }
+
+        let upper = colors.split_off(split);
+        boxes.push(ColorBox::new(colors));
+        boxes.push(ColorBox::new(upper));
+    }
+
+    boxes
+        .iter()
+        .map(|ColorBox { colors, .. }| {
+            let total = colors.iter().map(|&(_, count)| count as u64).sum::<u64>();
+            let sum = |c: usize| {
+                colors
+                    .iter()
+                    .map(|&(rgb, count)| channel(rgb, c) as u64 * count as u64)
+                    .sum::<u64>()
+            };
+
+            RGB::new(
+                ((sum(0) + total / 2) / total) as u8,
+                ((sum(1) + total / 2) / total) as u8,
+                ((sum(2) + total / 2) / total) as u8,
+            )
+        })
+        .collect()
+}
+
+// measures each entry once, this runs for every distinct color
+fn nearest(palette: &[Lab], color: Lab) -> usize {
+    let mut best = (0, f32::INFINITY);
+    for (i, entry) in palette.iter().enumerate() {
+        let distance = entry.distance(color);
+        if distance < best.1 {
+            best = (i, distance);
+        }
+    }
+    best.0
+}
+
+fn kmeans(histogram: &[(RGB, u32)], seed: Vec<RGB>) -> Vec<RGB> {
+    let samples = histogram
+        .iter()
+        .map(|&(rgb, count)| (rgb_to_lab(rgb.r as f32, rgb.g as f32, rgb.b as f32), count))
+        .collect::<Vec<_>>();
+    let mut palette = seed
+        .iter()
+        .map(|rgb| rgb_to_lab(rgb.r as f32, rgb.g as f32, rgb.b as f32))
+        .collect::<Vec<_>>();
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![(0.0, 0.0, 0.0, 0.0); palette.len()];
+        for &(lab, count) in &samples {
+            let sum = &mut sums[nearest(&palette, lab)];
+            let weight = count as f32;
+            sum.0 += lab.l * weight;
+            sum.1 += lab.a * weight;
+            sum.2 += lab.b * weight;
+            sum.3 += weight;
+        }
+
+        let mut moved = false;
+        for (color, &(l, a, b, weight)) in palette.iter_mut().zip(&sums) {
+            // empty clusters keep their previous center
+            if weight == 0.0 {
+                continue;
+            }
+
+            let center = Lab {
+                l: l / weight,
+                a: a / weight,
+                b: b / weight,
+            };
+            moved |= center.distance(*color) > 0.01;
+            *color = center;
+        }
+
+        if !moved {
+            break;
+        }
+    }
+
+    let mut palette = palette.into_iter().map(lab_to_rgb).collect::<Vec<_>>();
+    palette.sort_by_key(|rgb| (rgb.r, rgb.g, rgb.b));
+    palette.dedup();
+    palette
+}
+
+pub fn quantize(image: ImageData, colors: usize, dither: bool) -> ImageData {
+    let mut counts: HashMap<RGB, u32> = HashMap::new();
+    for pixel in image.pixels.iter().flatten() {
+        *counts.entry(*pixel).or_default() += 1;
+    }
+
+    if counts.len() <= colors {
+        return image;
+    }
+
+    let mut histogram = if counts.len() > MAX_HISTOGRAM_COLORS {
+        bucket_histogram(&counts)
+    } else {
+        counts.into_iter().collect::<Vec<_>>()
+    };
+    histogram.sort_by_key(|&(rgb, _)| (rgb.r, rgb.g, rgb.b));
+
+    let palette = kmeans(&histogram, median_cut(&histogram, colors));
+    let palette_lab = palette
+        .iter()
+        .map(|rgb| rgb_to_lab(rgb.r as f32, rgb.g as f32, rgb.b as f32))
+        .collect::<Vec<_>>();
+
+    let pixels = if dither {
+        dither_pixels(&image, &palette, &palette_lab)
+    } else {
+        let mut cache: HashMap<RGB, RGB> = HashMap::new();
+        image
+            .pixels
+            .iter()
+            .map(|pixel| {
+                pixel.map(|rgb| {
+                    *cache.entry(rgb).or_insert_with(|| {
+                        palette[nearest(
+                            &palette_lab,
+                            rgb_to_lab(rgb.r as f32, rgb.g as f32, rgb.b as f32),
+                        )]
+                    })
+                })
+            })
+            .collect()
+    };
+
+    ImageData {
+        width: image.width,
+        height: image.height,
+        pixels,
+    }
+}
+
+// Floyd-Steinberg, transparent pixels neither receive nor spread error
+fn dither_pixels(image: &ImageData, palette: &[RGB], palette_lab: &[Lab]) -> Vec<Option<RGB>> {
+    let (width, height) = (image.width, image.height);
+    let mut errors = vec![[0.0f32; 3]; width * height];
+    let mut pixels = vec![None; width * height];
+
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let Some(rgb) = image.pixels[i] else {
+                continue;
+            };
+
+            let wanted = [
+                (rgb.r as f32 + errors[i][0]).clamp(0.0, 255.0),
+                (rgb.g as f32 + errors[i][1]).clamp(0.0, 255.0),
+                (rgb.b as f32 + errors[i][2]).clamp(0.0, 255.0),
+            ];
+            let chosen = palette[nearest(palette_lab, rgb_to_lab(wanted[0], wanted[1], wanted[2]))];
+            pixels[i] = Some(chosen);
+
+            let error = [
+                wanted[0] - chosen.r as f32,
+                wanted[1] - chosen.g as f32,
+                wanted[2] - chosen.b as f32,
+            ];
+
+            let mut spread = |dx: isize, dy: usize, weight: f32| {
+                let nx = x as isize + dx;
+                let ny = y + dy;
+                if nx < 0 || nx >= width as isize || ny >= height {
+                    return;
+                }
+
+                let target = &mut errors[ny * width + nx as usize];
+                for c in 0..3 {
+                    target[c] += error[c] * weight;
+                }
+            };
+
+            spread(1, 0, 7.0 / 16.0);
+            spread(-1, 1, 3.0 / 16.0);
+            spread(0, 1, 5.0 / 16.0);
+            spread(1, 1, 1.0 / 16.0);
+        }
+    }
+
+    pixels
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::{board::ImageData, palette::RGB};
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResampleMode {
+    // pick the source pixel under the center of each cell
+    Nearest,
+    // average every source pixel covered by the cell
+    #[default]
+    Box,
+    // most common source color covered by the cell, keeps palettes intact
+    Majority,
+}
+
+impl FromStr for ResampleMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nearest" => Ok(Self::Nearest),
+            "box" => Ok(Self::Box),
+            "majority" => Ok(Self::Majority),
+            _ => Err(()),
+        }
+    }
+}
+
+fn box_average(pixels: &[Option<RGB>]) -> Option<RGB> {
+    let opaque = pixels.iter().flatten().collect::<Vec<_>>();
+    // cells that are mostly transparent stay transparent
+    if opaque.len() * 2 < pixels.len() {
+        return None;
+    }
+
+    let count = opaque.len() as u64;
+    let sum = |channel: fn(&RGB) -> u8| {
+        let total = opaque.iter().map(|&rgb| channel(rgb) as u64).sum::<u64>();
+        ((total + count / 2) / count) as u8
+    };
+
+    Some(RGB::new(sum(|c| c.r), sum(|c| c.g), sum(|c| c.b)))
+}
+
+fn majority(pixels: &[Option<RGB>]) -> Option<RGB> {
+    let mut counts: HashMap<Option<RGB>, usize> = HashMap::new();
+    for &pixel in pixels {
+        *counts.entry(pixel).or_default() += 1;
+    }
+
+    // ties go to the color seen first so the result is deterministic
+    let mut best = pixels[0];
+    for &pixel in pixels {
+        if counts[&pixel] > counts[&best] {
+            best = pixel;
+        }
+    }
+
+    best
+}
+
+// shrinks the image so it fits in max_width x max_height, keeping its aspect
+// ratio, images that already fit are returned unchanged
+pub fn resample(
+    image: ImageData,
+    max_width: usize,
+    max_height: usize,
+    mode: ResampleMode,
+) -> ImageData {
+    let (width, height) = (image.width, image.height);
+    if width <= max_width && height <= max_height {
+        return image;
+    }
+
+    let scale = (max_width as f64 / width as f64).min(max_height as f64 / height as f64);
+    let target_width = ((width as f64 * scale).round() as usize).clamp(1, max_width);
+    let target_height = ((height as f64 * scale).round() as usize).clamp(1, max_height);
+
+    let mut pixels = Vec::with_capacity(target_width * target_height);
+    let mut area = Vec::new();
+
+    for ty in 0..target_height {
+        let y0 = ty * height / target_height;
+        let y1 = ((ty + 1) * height / target_height).max(y0 + 1);
+
+        for tx in 0..target_width {
+            let x0 = tx * width / target_width;
+            let x1 = ((tx + 1) * width / target_width).max(x0 + 1);
+
+            let pixel = match mode {
+                ResampleMode::Nearest => {
+                    let sx = (2 * tx + 1) * width / (2 * target_width);
+                    let sy = (2 * ty + 1) * height / (2 * target_height);
+                    image.pixels[sy * width + sx]
+                }
+                ResampleMode::Box | ResampleMode::Majority => {
+                    area.clear();
+                    for y in y0..y1 {
+                        area.extend_from_slice(&image.pixels[y * width + x0..y * width + x1]);
+                    }
+
+                    if mode == ResampleMode::Box {
+                        box_average(&area)
+                    } else {
+                        majority(&area)
+                    }
+                }
+            };
+
+            pixels.push(pixel);
+        }
+    }
+
+    ImageData {
+        width: target_width,
+        height: target_height,
+        pixels,
+    }
+}
@@ -0,0 +1,255 @@
+// puzzle progress is stored in the user data directory, one file per
+// puzzle, named after a hash of the puzzle so the same image picks its
+// progress back up no matter where it is opened from
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    iter::Peekable,
+    path::PathBuf,
+    str::Lines,
+};
+
+use crate::{
+    board::{Board, BoardData, RenderMode},
+    palette::ColorId,
+};
+
+const HEADER: &str = "pixel-tui save 1";
+
+// FNV-1a, stable across builds unlike std's DefaultHasher
+struct Hasher(u64);
+
+impl Hasher {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+// hash of the puzzle as it is played, after resampling and quantization
+pub fn puzzle_hash(data: &BoardData) -> u64 {
+    let mut hasher = Hasher::new();
+    hasher.write(&(data.width as u64).to_le_bytes());
+    hasher.write(&(data.height as u64).to_le_bytes());
+
+    for color in &data.colors {
+        hasher.write(&[color.rgb.r, color.rgb.g, color.rgb.b]);
+    }
+    for pixel in data.pixels.iter().flatten() {
+        match pixel {
+            Some(color) => hasher.write(&color.0.to_le_bytes()),
+            None => hasher.write(&[0xff, 0xff, 0xff]),
+        }
+    }
+
+    hasher.0
+}
+
+fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("APPDATA").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+
+    env::var_os("HOME")
+        .filter(|d| !d.is_empty())
+        .map(|home| PathBuf::from(home).join(".local").join("share"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid save file: {message}"),
+    )
+}
+
+// values of the next line when it is the given field, None when it is
+// another one, which is how fields older saves don't have are left out
+fn optional_field(lines: &mut Peekable<Lines>, name: &str) -> io::Result<Option<Vec<String>>> {
+    let line = lines
+        .peek()
+        .ok_or_else(|| invalid("unexpected end of file"))?;
+    let mut parts = line.split_whitespace();
+    if parts.next() != Some(name) {
+        return Ok(None);
+    }
+
+    let values = parts.map(str::to_string).collect();
+    lines.next();
+    Ok(Some(values))
+}
+
+fn field(lines: &mut Peekable<Lines>, name: &str) -> io::Result<Vec<String>> {
+    optional_field(lines, name)?.ok_or_else(|| invalid(&format!("expected '{name}'")))
+}
+
+pub struct SaveFile {
+    pub path: PathBuf,
+    hash: u64,
+}
+
+impl SaveFile {
+    pub fn for_puzzle(hash: u64) -> Option<Self> {
+        let path = data_dir()?
+            .join("pixel-tui")
+            .join("saves")
+            .join(format!("{hash:016x}.save"));
+
+        Some(Self { path, hash })
+    }
+
+    pub fn store(&self, board: &Board) -> io::Result<()> {
+        let mut contents = String::new();
+        contents.push_str(HEADER);
+        contents.push('\n');
+        contents.push_str(&format!("hash {:016x}\n", self.hash));
+        contents.push_str(&format!("size {} {}\n", board.width, board.height));
+        contents.push_str(&format!("selected {}\n", board.palette.selected_color.0));
+        // the offset is in terminal cells, which only line up with the
+        // same render mode and zoom
+        let mode = match board.render_mode {
+            RenderMode::Blocks => "blocks",
+            RenderMode::HalfBlocks => "half-blocks",
+        };
+        contents.push_str(&format!("mode {mode}\n"));
+        contents.push_str(&format!("zoom {}\n", board.zoom));
+        contents.push_str(&format!("offset {} {}\n", board.x, board.y));
+        contents.push_str(&format!("mistakes {}\n", board.mistakes));
+        contents.push_str("painted\n");
+        for row in &board.cells {
+            let row = row
+                .iter()
+                .map(|cell| match cell.painted {
+                    Some(color) => color.0.to_string(),
+                    None => ".".to_string(),
+                })
+                .collect::<Vec<_>>();
+            contents.push_str(&row.join(" "));
+            contents.push('\n');
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // write then rename so a crash never leaves a half written save
+        let tmp_path = self.path.with_extension("save.tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(tmp_path, &self.path)
+    }
+
+    // returns false when there is no progress to restore
+    pub fn restore(&self, board: &mut Board) -> io::Result<bool> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        let mut lines = contents.lines().peekable();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid("unknown header"));
+        }
+
+        let number = |value: Option<&String>| -> io::Result<i64> {
+            value
+                .and_then(|v| v.parse::<i64>().ok())
+                .ok_or_else(|| invalid("expected a number"))
+        };
+
+        let hash = field(&mut lines, "hash")?;
+        if hash.first().and_then(|h| u64::from_str_radix(h, 16).ok()) != Some(self.hash) {
+            return Ok(false);
+        }
+
+        let size = field(&mut lines, "size")?;
+        if number(size.first())? != board.width as i64
+            || number(size.get(1))? != board.height as i64
+        {
+            return Ok(false);
+        }
+
+        let selected = number(field(&mut lines, "selected")?.first())?;
+
+        // saves from before the view was stored keep the default one
+        let render_mode = match optional_field(&mut lines, "mode")? {
+            Some(mode) => match mode.first().map(String::as_str) {
+                Some("blocks") => RenderMode::Blocks,
+                Some("half-blocks") => RenderMode::HalfBlocks,
+                _ => return Err(invalid("unknown render mode")),
+            },
+            None => board.render_mode,
+        };
+        let zoom = match optional_field(&mut lines, "zoom")? {
+            Some(zoom) => number(zoom.first())?,
+            None => board.zoom as i64,
+        };
+        if !(0..render_mode.zoom_levels().len() as i64).contains(&zoom) {
+            return Err(invalid("bad zoom level"));
+        }
+
+        let offset = field(&mut lines, "offset")?;
+        let (x, y) = (number(offset.first())?, number(offset.get(1))?);
+        let mistakes = number(field(&mut lines, "mistakes")?.first())?;
+        field(&mut lines, "painted")?;
+        let colors = board.palette.colors.len();
+        let mut painted = Vec::with_capacity(board.height);
+        for row in &board.cells {
+            let line = lines
+                .next()
+                .ok_or_else(|| invalid("missing painted rows"))?;
+            let values = line.split_whitespace().collect::<Vec<_>>();
+            if values.len() != board.width {
+                return Err(invalid("painted row has the wrong length"));
+            }
+
+            let mut painted_row = Vec::with_capacity(board.width);
+            for (value, cell) in values.into_iter().zip(row) {
+                let color = match value {
+                    "." => None,
+                    _ => match value.parse::<usize>() {
+                        Ok(color) if color < colors && cell.color.is_some() => {
+                            Some(ColorId(color as u16))
+                        }
+                        _ => return Err(invalid("bad painted color")),
+                    },
+                };
+                painted_row.push(color);
+            }
+            painted.push(painted_row);
+        }
+
+        for (row, painted) in board.cells.iter_mut().zip(painted) {
+            for (cell, painted) in row.iter_mut().zip(painted) {
+                cell.painted = painted;
+            }
+        }
+        board.recount_painted();
+
+        board.mistakes = mistakes.clamp(0, u32::MAX as i64) as u32;
+        if (0..board.palette.colors.len() as i64).contains(&selected) {
+            board.palette.selected_color = ColorId(selected as u16);
+        }
+        board.render_mode = render_mode;
+        board.zoom = zoom as usize;
+        // no terminal is bigger than u16::MAX cells, anything further out
+        // only leaves the board off screen and the math near overflowing
+        let (width, height) = board.size_on_screen();
+        let max = u16::MAX as i64;
+        board.x = x.clamp(-(width as i64), max) as i32;
+        board.y = y.clamp(-(height as i64), max) as i32;
+
+        Ok(true)
+    }
+}
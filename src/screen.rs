@@ -0,0 +1,169 @@
+// back buffer the board and palette draw into, only the cells that changed
+// since the last frame are written to the terminal
+
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor, queue,
+    style::{self, Attribute, Attributes, Color, Colors},
+    terminal::{self, ClearType},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub attrs: Attributes,
+}
+
+impl Style {
+    pub const fn new(fg: Color, bg: Color) -> Self {
+        Self {
+            fg,
+            bg,
+            attrs: Attributes::none(),
+        }
+    }
+
+    pub fn fg(fg: Color) -> Self {
+        Self::new(fg, Color::Reset)
+    }
+
+    pub fn bg(bg: Color) -> Self {
+        Self::new(Color::Reset, bg)
+    }
+
+    pub fn bold(self) -> Self {
+        Self {
+            attrs: self.attrs.with(Attribute::Bold),
+            ..self
+        }
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self::new(Color::Reset, Color::Reset)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ScreenCell {
+    pub glyph: char,
+    pub style: Style,
+}
+
+const BLANK: ScreenCell = ScreenCell {
+    glyph: ' ',
+    style: Style::new(Color::Reset, Color::Reset),
+};
+
+pub struct Screen {
+    width: u16,
+    height: u16,
+    cells: Vec<ScreenCell>,
+    // what the terminal currently shows
+    shown: Vec<ScreenCell>,
+    redraw: bool,
+}
+
+impl Screen {
+    pub fn new(size: (u16, u16)) -> Self {
+        let mut screen = Self {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+            shown: Vec::new(),
+            redraw: true,
+        };
+        screen.resize(size);
+        screen
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    // the next flush clears the terminal and writes every cell
+    pub fn resize(&mut self, size: (u16, u16)) {
+        let len = size.0 as usize * size.1 as usize;
+        (self.width, self.height) = size;
+        self.cells = vec![BLANK; len];
+        self.shown = vec![BLANK; len];
+        self.redraw = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(BLANK);
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        ((0..self.width as i32).contains(&x) && (0..self.height as i32).contains(&y))
+            .then(|| y as usize * self.width as usize + x as usize)
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<ScreenCell> {
+        self.index(x, y).map(|i| self.cells[i])
+    }
+
+    // one cell per char, anything outside the screen is dropped
+    pub fn print(&mut self, x: i32, y: i32, text: &str, style: Style) {
+        for (i, glyph) in text.chars().enumerate() {
+            if let Some(index) = self.index(x + i as i32, y) {
+                self.cells[index] = ScreenCell { glyph, style };
+            }
+        }
+    }
+
+    // returns false when nothing changed and nothing was written
+    pub fn flush(&mut self, stdout: &mut impl Write) -> io::Result<bool> {
+        let changed = self.redraw || self.cells != self.shown;
+        if !changed {
+            return Ok(false);
+        }
+
+        queue!(stdout, terminal::BeginSynchronizedUpdate)?;
+        if self.redraw {
+            queue!(stdout, terminal::Clear(ClearType::All))?;
+            self.shown.fill(BLANK);
+            self.redraw = false;
+        }
+
+        let mut position = None;
+        let mut current_style = None;
+        for (i, (&cell, shown)) in self.cells.iter().zip(&mut self.shown).enumerate() {
+            if cell == *shown {
+                continue;
+            }
+            *shown = cell;
+
+            let x = (i % self.width as usize) as u16;
+            let y = (i / self.width as usize) as u16;
+            if position != Some((x, y)) {
+                queue!(stdout, cursor::MoveTo(x, y))?;
+            }
+            if current_style != Some(cell.style) {
+                queue!(
+                    stdout,
+                    style::SetAttribute(Attribute::Reset),
+                    style::SetColors(Colors::new(cell.style.fg, cell.style.bg)),
+                    style::SetAttributes(cell.style.attrs),
+                )?;
+                current_style = Some(cell.style);
+            }
+
+            queue!(stdout, style::Print(cell.glyph))?;
+            position = Some((x + 1, y));
+        }
+
+        queue!(
+            stdout,
+            style::SetAttribute(Attribute::Reset),
+            style::ResetColor,
+            terminal::EndSynchronizedUpdate
+        )?;
+        stdout.flush()?;
+
+        Ok(true)
+    }
+}